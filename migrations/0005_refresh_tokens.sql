@@ -0,0 +1,4 @@
+ALTER TABLE sessions ADD COLUMN refresh_token_hash TEXT;
+ALTER TABLE sessions ADD COLUMN refresh_expires_at DATETIME;
+
+CREATE UNIQUE INDEX IF NOT EXISTS sessions_refresh_token_hash ON sessions (refresh_token_hash);
@@ -0,0 +1,23 @@
+ALTER TABLE users ADD COLUMN is_admin INTEGER NOT NULL DEFAULT 0;
+
+CREATE TABLE sessions_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    token_hash TEXT NOT NULL UNIQUE,
+    username TEXT NOT NULL,
+    created_at DATETIME NOT NULL,
+    last_used_at DATETIME NOT NULL,
+    expires_at DATETIME NOT NULL,
+    refresh_token_hash TEXT UNIQUE,
+    refresh_expires_at DATETIME
+);
+
+INSERT INTO sessions_new
+SELECT id, token_hash, username, created_at, last_used_at, expires_at, refresh_token_hash, refresh_expires_at
+FROM sessions WHERE username IS NOT NULL;
+
+DROP TABLE sessions;
+
+ALTER TABLE sessions_new RENAME TO sessions;
+
+CREATE INDEX IF NOT EXISTS sessions_username ON sessions (username);
+CREATE INDEX IF NOT EXISTS sessions_expires_at ON sessions (expires_at);
@@ -0,0 +1,5 @@
+ALTER TABLE users ADD COLUMN username_folded TEXT NOT NULL DEFAULT '';
+
+UPDATE users SET username_folded = lower(username);
+
+CREATE INDEX IF NOT EXISTS users_username_folded ON users (username_folded);
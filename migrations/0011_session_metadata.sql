@@ -0,0 +1,3 @@
+ALTER TABLE sessions ADD COLUMN client_name TEXT;
+ALTER TABLE sessions ADD COLUMN user_agent TEXT;
+ALTER TABLE sessions ADD COLUMN ip TEXT;
@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS audit_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    created_at DATETIME NOT NULL,
+    actor TEXT,
+    action TEXT NOT NULL,
+    target TEXT,
+    details TEXT
+);
+
+CREATE INDEX IF NOT EXISTS audit_events_created_at ON audit_events (created_at);
+CREATE INDEX IF NOT EXISTS audit_events_actor ON audit_events (actor);
+CREATE INDEX IF NOT EXISTS audit_events_action ON audit_events (action);
@@ -0,0 +1,19 @@
+CREATE TABLE IF NOT EXISTS groups (
+    name TEXT PRIMARY KEY,
+    created_at DATETIME NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS group_members (
+    group_name TEXT NOT NULL,
+    username TEXT NOT NULL,
+    PRIMARY KEY (group_name, username)
+);
+
+CREATE TABLE IF NOT EXISTS audiobook_groups (
+    audiobook_hash TEXT NOT NULL,
+    group_name TEXT NOT NULL,
+    PRIMARY KEY (audiobook_hash, group_name)
+);
+
+CREATE INDEX IF NOT EXISTS group_members_username ON group_members (username);
+CREATE INDEX IF NOT EXISTS audiobook_groups_group_name ON audiobook_groups (group_name);
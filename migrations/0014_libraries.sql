@@ -0,0 +1,3 @@
+ALTER TABLE audiobooks ADD COLUMN library TEXT NOT NULL DEFAULT 'default';
+
+CREATE INDEX IF NOT EXISTS audiobooks_library ON audiobooks (library);
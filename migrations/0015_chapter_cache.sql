@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS chapter_cache (
+    path TEXT PRIMARY KEY,
+    size INTEGER NOT NULL,
+    mtime_ns INTEGER NOT NULL,
+    duration_us INTEGER NOT NULL
+);
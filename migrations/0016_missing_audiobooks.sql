@@ -0,0 +1,2 @@
+ALTER TABLE audiobooks ADD COLUMN missing_since DATETIME;
+ALTER TABLE audiobooks ADD COLUMN missing_reason TEXT;
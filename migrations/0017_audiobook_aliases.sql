@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS audiobook_aliases (
+    alias TEXT PRIMARY KEY,
+    hash TEXT NOT NULL,
+    created_at DATETIME NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS audiobook_aliases_hash ON audiobook_aliases (hash);
@@ -1,16 +1,45 @@
 use crate::error::AppError;
 use dotenvy::dotenv;
 use std::env;
+use std::net::IpAddr;
 
 #[derive(Debug, Clone)]
 pub struct Config {
     pub db_path: String,
-    pub library_path: String,
+    pub libraries: Vec<(String, String)>,
+    pub scan_depth: usize,
+    pub scan_parallelism: usize,
+    pub missing_grace_days: u64,
+    pub watch_library: bool,
+    pub watch_debounce_seconds: u64,
+    pub scan_interval_minutes: u64,
+    pub cleanup_interval_minutes: u64,
     pub server_address: String,
     pub server_port: u16,
     pub public_register: bool,
-    pub admin_password: String,
+    pub admin_username: String,
+    pub admin_password: Option<String>,
     pub token_ttl_hours: u64,
+    pub refresh_ttl_days: u64,
+    pub sliding_expiry: bool,
+    pub login_max_attempts: u32,
+    pub login_lockout_seconds: u64,
+    pub login_lockout_max_seconds: u64,
+    pub username_min_length: usize,
+    pub username_max_length: usize,
+    pub username_extra_chars: String,
+    pub password_min_length: usize,
+    pub breached_passwords_path: Option<String>,
+    pub oidc_issuer: Option<String>,
+    pub oidc_client_id: Option<String>,
+    pub oidc_client_secret: Option<String>,
+    pub oidc_redirect_url: Option<String>,
+    pub oidc_scopes: String,
+    pub oidc_username_claim: String,
+    pub oidc_auto_provision: bool,
+    pub oidc_link_existing: bool,
+    pub auth_proxy_header: Option<String>,
+    pub trusted_proxies: Vec<IpAddr>,
 }
 
 impl Config {
@@ -27,25 +56,191 @@ impl Config {
             .parse::<bool>()
             .map_err(|e| AppError::Internal(format!("invalid ILIAD_PUBLIC_REGISTER: {e}")))?;
 
-        let admin_password = env::var("ILIAD_ADMIN_PASSWORD")
-            .map_err(|_| AppError::Internal("ILIAD_ADMIN_PASSWORD must be set".to_string()))?;
+        let admin_password = env::var("ILIAD_ADMIN_PASSWORD").ok();
 
         let token_ttl_hours = env::var("ILIAD_TOKEN_TTL_HOURS")
             .unwrap_or_else(|_| "24".to_string())
             .parse::<u64>()
             .map_err(|e| AppError::Internal(format!("invalid ILIAD_TOKEN_TTL_HOURS: {e}")))?;
 
+        let refresh_ttl_days = env::var("ILIAD_REFRESH_TTL_DAYS")
+            .unwrap_or_else(|_| "30".to_string())
+            .parse::<u64>()
+            .map_err(|e| AppError::Internal(format!("invalid ILIAD_REFRESH_TTL_DAYS: {e}")))?;
+
+        let sliding_expiry = env::var("ILIAD_SLIDING_EXPIRY")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse::<bool>()
+            .map_err(|e| AppError::Internal(format!("invalid ILIAD_SLIDING_EXPIRY: {e}")))?;
+
+        let login_max_attempts = env::var("ILIAD_LOGIN_MAX_ATTEMPTS")
+            .unwrap_or_else(|_| "5".to_string())
+            .parse::<u32>()
+            .map_err(|e| AppError::Internal(format!("invalid ILIAD_LOGIN_MAX_ATTEMPTS: {e}")))?;
+
+        let login_lockout_seconds = env::var("ILIAD_LOGIN_LOCKOUT_SECONDS")
+            .unwrap_or_else(|_| "30".to_string())
+            .parse::<u64>()
+            .map_err(|e| AppError::Internal(format!("invalid ILIAD_LOGIN_LOCKOUT_SECONDS: {e}")))?;
+
+        let login_lockout_max_seconds = env::var("ILIAD_LOGIN_LOCKOUT_MAX_SECONDS")
+            .unwrap_or_else(|_| "900".to_string())
+            .parse::<u64>()
+            .map_err(|e| {
+                AppError::Internal(format!("invalid ILIAD_LOGIN_LOCKOUT_MAX_SECONDS: {e}"))
+            })?;
+
+        let username_min_length = env::var("ILIAD_USERNAME_MIN_LENGTH")
+            .unwrap_or_else(|_| "2".to_string())
+            .parse::<usize>()
+            .map_err(|e| AppError::Internal(format!("invalid ILIAD_USERNAME_MIN_LENGTH: {e}")))?;
+
+        let username_max_length = env::var("ILIAD_USERNAME_MAX_LENGTH")
+            .unwrap_or_else(|_| "32".to_string())
+            .parse::<usize>()
+            .map_err(|e| AppError::Internal(format!("invalid ILIAD_USERNAME_MAX_LENGTH: {e}")))?;
+
+        let password_min_length = env::var("ILIAD_PASSWORD_MIN_LENGTH")
+            .unwrap_or_else(|_| "8".to_string())
+            .parse::<usize>()
+            .map_err(|e| AppError::Internal(format!("invalid ILIAD_PASSWORD_MIN_LENGTH: {e}")))?;
+
+        let oidc_auto_provision = env::var("ILIAD_OIDC_AUTO_PROVISION")
+            .unwrap_or_else(|_| "true".to_string())
+            .parse::<bool>()
+            .map_err(|e| AppError::Internal(format!("invalid ILIAD_OIDC_AUTO_PROVISION: {e}")))?;
+
+        let oidc_link_existing = env::var("ILIAD_OIDC_LINK_EXISTING")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse::<bool>()
+            .map_err(|e| AppError::Internal(format!("invalid ILIAD_OIDC_LINK_EXISTING: {e}")))?;
+
+        // ILIAD_LIBRARY_PATHS is a comma separated list of name=path pairs; without
+        // it ILIAD_LIBRARY_PATH is served as a single library called "default".
+        let libraries = match env::var("ILIAD_LIBRARY_PATHS") {
+            Ok(value) => {
+                let mut libraries: Vec<(String, String)> = Vec::new();
+                for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+                    let (name, path) = entry
+                        .split_once('=')
+                        .map(|(n, p)| (n.trim(), p.trim()))
+                        .filter(|(n, p)| !n.is_empty() && !p.is_empty())
+                        .ok_or_else(|| {
+                            AppError::Internal(format!("invalid ILIAD_LIBRARY_PATHS entry: {entry}"))
+                        })?;
+                    if libraries.iter().any(|(n, _)| n == name) {
+                        return Err(AppError::Internal(format!(
+                            "duplicate library name in ILIAD_LIBRARY_PATHS: {name}"
+                        )));
+                    }
+                    libraries.push((name.to_string(), path.to_string()));
+                }
+                if libraries.is_empty() {
+                    return Err(AppError::Internal("ILIAD_LIBRARY_PATHS is empty".into()));
+                }
+                libraries
+            }
+            Err(_) => vec![(
+                "default".to_string(),
+                env::var("ILIAD_LIBRARY_PATH").unwrap_or_else(|_| "/app/instance/library".to_string()),
+            )],
+        };
+
+        let scan_depth = env::var("ILIAD_SCAN_DEPTH")
+            .unwrap_or_else(|_| "3".to_string())
+            .parse::<usize>()
+            .map_err(|e| AppError::Internal(format!("invalid ILIAD_SCAN_DEPTH: {e}")))?;
+
+        // Defaults to one probe per available core.
+        let scan_parallelism = match env::var("ILIAD_SCAN_PARALLELISM") {
+            Ok(value) => value
+                .parse::<usize>()
+                .ok()
+                .filter(|n| *n > 0)
+                .ok_or_else(|| {
+                    AppError::Internal(format!("invalid ILIAD_SCAN_PARALLELISM: {value}"))
+                })?,
+            Err(_) => std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+        };
+
+        let missing_grace_days = env::var("ILIAD_MISSING_GRACE_DAYS")
+            .unwrap_or_else(|_| "7".to_string())
+            .parse::<u64>()
+            .map_err(|e| AppError::Internal(format!("invalid ILIAD_MISSING_GRACE_DAYS: {e}")))?;
+
+        let watch_library = env::var("ILIAD_WATCH_LIBRARY")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse::<bool>()
+            .map_err(|e| AppError::Internal(format!("invalid ILIAD_WATCH_LIBRARY: {e}")))?;
+
+        let watch_debounce_seconds = env::var("ILIAD_WATCH_DEBOUNCE_SECONDS")
+            .unwrap_or_else(|_| "10".to_string())
+            .parse::<u64>()
+            .map_err(|e| AppError::Internal(format!("invalid ILIAD_WATCH_DEBOUNCE_SECONDS: {e}")))?;
+
+        let scan_interval_minutes = env::var("ILIAD_SCAN_INTERVAL_MINUTES")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse::<u64>()
+            .map_err(|e| AppError::Internal(format!("invalid ILIAD_SCAN_INTERVAL_MINUTES: {e}")))?;
+
+        let cleanup_interval_minutes = env::var("ILIAD_CLEANUP_INTERVAL_MINUTES")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse::<u64>()
+            .map_err(|e| {
+                AppError::Internal(format!("invalid ILIAD_CLEANUP_INTERVAL_MINUTES: {e}"))
+            })?;
+
+        let trusted_proxies = env::var("ILIAD_TRUSTED_PROXIES")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|ip| !ip.is_empty())
+            .map(|ip| ip.parse::<IpAddr>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::Internal(format!("invalid ILIAD_TRUSTED_PROXIES: {e}")))?;
+
         Ok(Config {
             db_path: env::var("ILIAD_DB_PATH")
                 .unwrap_or_else(|_| "/app/instance/iliad.db".to_string()),
-            library_path: env::var("ILIAD_LIBRARY_PATH")
-                .unwrap_or_else(|_| "/app/instance/library".to_string()),
+            libraries,
+            scan_depth,
+            scan_parallelism,
+            missing_grace_days,
+            watch_library,
+            watch_debounce_seconds,
+            scan_interval_minutes,
+            cleanup_interval_minutes,
             server_address: env::var("ILIAD_SERVER_ADDRESS")
                 .unwrap_or_else(|_| "0.0.0.0".to_string()),
             server_port,
             public_register,
+            admin_username: env::var("ILIAD_ADMIN_USERNAME")
+                .unwrap_or_else(|_| "admin".to_string()),
             admin_password,
             token_ttl_hours,
+            refresh_ttl_days,
+            sliding_expiry,
+            login_max_attempts,
+            login_lockout_seconds,
+            login_lockout_max_seconds,
+            username_min_length,
+            username_max_length,
+            username_extra_chars: env::var("ILIAD_USERNAME_EXTRA_CHARS")
+                .unwrap_or_else(|_| "._-".to_string()),
+            password_min_length,
+            breached_passwords_path: env::var("ILIAD_BREACHED_PASSWORDS_PATH").ok(),
+            oidc_issuer: env::var("ILIAD_OIDC_ISSUER").ok(),
+            oidc_client_id: env::var("ILIAD_OIDC_CLIENT_ID").ok(),
+            oidc_client_secret: env::var("ILIAD_OIDC_CLIENT_SECRET").ok(),
+            oidc_redirect_url: env::var("ILIAD_OIDC_REDIRECT_URL").ok(),
+            oidc_scopes: env::var("ILIAD_OIDC_SCOPES")
+                .unwrap_or_else(|_| "openid profile email".to_string()),
+            oidc_username_claim: env::var("ILIAD_OIDC_USERNAME_CLAIM")
+                .unwrap_or_else(|_| "preferred_username".to_string()),
+            oidc_auto_provision,
+            oidc_link_existing,
+            auth_proxy_header: env::var("ILIAD_AUTH_PROXY_HEADER").ok(),
+            trusted_proxies,
         })
     }
 }
@@ -1,4 +1,4 @@
-use crate::error::AppError;
+use crate::{error::AppError, repo::user as user_repo};
 use sqlx::{sqlite::SqliteConnectOptions, SqlitePool};
 use std::str::FromStr;
 
@@ -11,5 +11,27 @@ pub async fn connect(db_path: &str) -> Result<SqlitePool, AppError> {
         .run(&pool)
         .await
         .map_err(|e| AppError::Internal(e.to_string()))?;
+    fold_usernames(&pool).await?;
     Ok(pool)
 }
+
+// Folding uses Unicode rules that SQL cannot express, so the folded usernames
+// are kept up to date here rather than in a migration. Accounts that collide
+// once folded must be renamed before the unique index can be created.
+async fn fold_usernames(pool: &SqlitePool) -> Result<(), AppError> {
+    let (updated, taken) = user_repo::refold_all(pool).await?;
+    if updated > 0 {
+        tracing::info!("updated folded form of {} usernames", updated);
+    }
+    for username in taken {
+        tracing::warn!("username {} is the same as another one ignoring case, rename it", username);
+    }
+    let duplicates = user_repo::find_folded_duplicates(pool).await?;
+    if duplicates.is_empty() {
+        return user_repo::create_folded_index(pool).await;
+    }
+    for usernames in duplicates {
+        tracing::warn!("usernames {} are the same ignoring case, rename all but one of them", usernames);
+    }
+    Ok(())
+}
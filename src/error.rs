@@ -1,6 +1,13 @@
 use actix_web::HttpResponse;
+use serde::Serialize;
 use thiserror::Error;
 
+#[derive(Serialize, Debug)]
+pub struct FieldError {
+    pub field: &'static str,
+    pub message: String,
+}
+
 #[derive(Debug, Error)]
 pub enum AppError {
     #[error("not found")]
@@ -15,6 +22,10 @@ pub enum AppError {
     ServiceUnavailable,
     #[error("{0}")]
     BadRequest(String),
+    #[error("invalid input")]
+    InvalidInput(Vec<FieldError>),
+    #[error("too many requests")]
+    TooManyRequests(u64),
 }
 
 impl actix_web::ResponseError for AppError {
@@ -25,6 +36,12 @@ impl actix_web::ResponseError for AppError {
             AppError::Conflict => HttpResponse::Conflict().body(self.to_string()),
             AppError::ServiceUnavailable => HttpResponse::ServiceUnavailable().body(self.to_string()),
             AppError::BadRequest(msg) => HttpResponse::BadRequest().body(msg.clone()),
+            AppError::InvalidInput(errors) => {
+                HttpResponse::BadRequest().json(serde_json::json!({ "errors": errors }))
+            }
+            AppError::TooManyRequests(retry_after) => HttpResponse::TooManyRequests()
+                .insert_header((actix_web::http::header::RETRY_AFTER, retry_after.to_string()))
+                .body(self.to_string()),
             AppError::Internal(msg) => {
                 tracing::error!("{}", msg);
                 HttpResponse::InternalServerError().body("internal server error")
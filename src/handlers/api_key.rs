@@ -0,0 +1,50 @@
+use actix_web::{web, HttpMessage, HttpRequest, HttpResponse};
+
+use crate::error::AppError;
+use crate::inputs::api_key::ApiKeyCreate;
+use crate::services::api_key::{create_api_key, list_api_keys, revoke_api_key};
+use crate::state::AppState;
+
+pub async fn get_api_keys(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+) -> Result<HttpResponse, AppError> {
+    let user = req
+        .extensions()
+        .get::<String>()
+        .cloned()
+        .ok_or(AppError::Unauthorized)?;
+
+    let keys = list_api_keys(user, &state).await?;
+    Ok(HttpResponse::Ok().json(keys))
+}
+
+pub async fn post_api_key(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    body: web::Json<ApiKeyCreate>,
+) -> Result<HttpResponse, AppError> {
+    let user = req
+        .extensions()
+        .get::<String>()
+        .cloned()
+        .ok_or(AppError::Unauthorized)?;
+
+    let key = create_api_key(user, body.into_inner(), &state).await?;
+    Ok(HttpResponse::Ok().json(key))
+}
+
+pub async fn delete_api_key(
+    state: web::Data<AppState>,
+    path: web::Path<i64>,
+    req: HttpRequest,
+) -> Result<HttpResponse, AppError> {
+    let user = req
+        .extensions()
+        .get::<String>()
+        .cloned()
+        .ok_or(AppError::Unauthorized)?;
+
+    revoke_api_key(user, path.into_inner(), &state).await?;
+    Ok(HttpResponse::Ok().finish())
+}
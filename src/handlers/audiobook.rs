@@ -1,21 +1,39 @@
 use actix_files::NamedFile;
 use actix_web::http::header::{ContentDisposition, DispositionParam, DispositionType};
-use actix_web::{web, HttpRequest, HttpResponse};
+use actix_web::{web, HttpMessage, HttpRequest, HttpResponse};
 
 use crate::error::AppError;
+use crate::inputs::audiobook::AudiobookQuery;
 use crate::services::audiobook::{get_audiobook_archive, get_audiobook_by_hash, list_audiobooks};
 use crate::state::AppState;
 
-pub async fn get_audiobooks(state: web::Data<AppState>) -> Result<HttpResponse, AppError> {
-    let audiobooks = list_audiobooks(&state).await?;
+pub async fn get_audiobooks(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    query: web::Query<AudiobookQuery>,
+) -> Result<HttpResponse, AppError> {
+    let user = req
+        .extensions()
+        .get::<String>()
+        .cloned()
+        .ok_or(AppError::Unauthorized)?;
+
+    let audiobooks = list_audiobooks(user, query.into_inner(), &state).await?;
     Ok(HttpResponse::Ok().json(audiobooks))
 }
 
 pub async fn get_audiobook(
     state: web::Data<AppState>,
+    req: HttpRequest,
     path: web::Path<String>,
 ) -> Result<HttpResponse, AppError> {
-    let book = get_audiobook_by_hash(path.into_inner(), &state).await?;
+    let user = req
+        .extensions()
+        .get::<String>()
+        .cloned()
+        .ok_or(AppError::Unauthorized)?;
+
+    let book = get_audiobook_by_hash(user, path.into_inner(), &state).await?;
     Ok(HttpResponse::Ok().json(book))
 }
 
@@ -24,7 +42,13 @@ pub async fn get_audiobook_download(
     state: web::Data<AppState>,
     path: web::Path<String>,
 ) -> Result<HttpResponse, AppError> {
-    let (filename, archive_path) = get_audiobook_archive(path.into_inner(), &state).await?;
+    let user = req
+        .extensions()
+        .get::<String>()
+        .cloned()
+        .ok_or(AppError::Unauthorized)?;
+
+    let (filename, archive_path) = get_audiobook_archive(user, path.into_inner(), &state).await?;
     let file = NamedFile::open(&archive_path)?.set_content_disposition(ContentDisposition {
         disposition: DispositionType::Attachment,
         parameters: vec![DispositionParam::Filename(filename)],
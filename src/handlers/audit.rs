@@ -0,0 +1,14 @@
+use actix_web::{web, HttpResponse};
+
+use crate::error::AppError;
+use crate::inputs::audit::AuditQuery;
+use crate::services::audit::list_events;
+use crate::state::AppState;
+
+pub async fn get_audit_events(
+    state: web::Data<AppState>,
+    query: web::Query<AuditQuery>,
+) -> Result<HttpResponse, AppError> {
+    let events = list_events(query.into_inner(), &state).await?;
+    Ok(HttpResponse::Ok().json(events))
+}
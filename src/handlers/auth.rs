@@ -1,30 +1,221 @@
-use actix_web::{web, HttpResponse};
+use std::net::{IpAddr, SocketAddr};
+
+use actix_web::{http::header, web, HttpMessage, HttpRequest, HttpResponse};
 
 use crate::error::AppError;
-use crate::inputs::auth::{AdminLogin, RegularLogin, RegularRegister};
-use crate::services::auth::{admin_login, login, register};
+use crate::inputs::auth::{
+    AccountDeletion, ClientInfo, PasswordChange, RegularLogin, RegularRegister, TokenRefresh,
+};
+use crate::models::session::Session;
+use crate::services::auth::{
+    change_password, delete_account, list_sessions, login, logout, logout_all, refresh, register,
+    revoke_session, revoke_user_sessions,
+};
 use crate::state::AppState;
 
 pub async fn post_auth_login(
     state: web::Data<AppState>,
+    req: HttpRequest,
     body: web::Json<RegularLogin>,
 ) -> Result<HttpResponse, AppError> {
-    let token = login(body.into_inner(), &state).await?;
+    let client = client_info(&req, body.client_name.clone());
+    let token = login(body.into_inner(), client, &state).await?;
     Ok(HttpResponse::Ok().json(token))
 }
 
-pub async fn post_auth_admin(
+pub async fn post_auth_register(
     state: web::Data<AppState>,
-    body: web::Json<AdminLogin>,
+    req: HttpRequest,
+    body: web::Json<RegularRegister>,
 ) -> Result<HttpResponse, AppError> {
-    let token = admin_login(body.into_inner(), &state).await?;
+    let caller = req.extensions().get::<String>().cloned();
+    let client = client_info(&req, body.client_name.clone());
+    let token = register(body.into_inner(), caller, client, &state).await?;
     Ok(HttpResponse::Ok().json(token))
 }
 
-pub async fn post_auth_register(
+pub async fn post_auth_refresh(
     state: web::Data<AppState>,
-    body: web::Json<RegularRegister>,
+    body: web::Json<TokenRefresh>,
 ) -> Result<HttpResponse, AppError> {
-    let token = register(body.into_inner(), &state).await?;
+    let token = refresh(body.into_inner(), &state).await?;
     Ok(HttpResponse::Ok().json(token))
 }
+
+pub async fn post_auth_logout(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+) -> Result<HttpResponse, AppError> {
+    let session = req
+        .extensions()
+        .get::<Session>()
+        .cloned()
+        .ok_or(AppError::Unauthorized)?;
+
+    logout(&session, &state).await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn post_auth_logout_all(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+) -> Result<HttpResponse, AppError> {
+    let session = req
+        .extensions()
+        .get::<Session>()
+        .cloned()
+        .ok_or(AppError::Unauthorized)?;
+
+    logout_all(&session, &state).await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn get_auth_sessions(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+) -> Result<HttpResponse, AppError> {
+    let user = req
+        .extensions()
+        .get::<String>()
+        .cloned()
+        .ok_or(AppError::Unauthorized)?;
+    let session = req.extensions().get::<Session>().cloned();
+
+    let sessions = list_sessions(user, session.as_ref(), &state).await?;
+    Ok(HttpResponse::Ok().json(sessions))
+}
+
+pub async fn delete_auth_session(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<i64>,
+) -> Result<HttpResponse, AppError> {
+    let user = req
+        .extensions()
+        .get::<String>()
+        .cloned()
+        .ok_or(AppError::Unauthorized)?;
+
+    revoke_session(user, path.into_inner(), &state).await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn put_auth_password(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    body: web::Json<PasswordChange>,
+) -> Result<HttpResponse, AppError> {
+    let user = req
+        .extensions()
+        .get::<String>()
+        .cloned()
+        .ok_or(AppError::Unauthorized)?;
+    let session = req
+        .extensions()
+        .get::<Session>()
+        .cloned()
+        .ok_or(AppError::Unauthorized)?;
+
+    change_password(user, &session, body.into_inner(), &state).await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn delete_auth_account(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    body: web::Json<AccountDeletion>,
+) -> Result<HttpResponse, AppError> {
+    let user = req
+        .extensions()
+        .get::<String>()
+        .cloned()
+        .ok_or(AppError::Unauthorized)?;
+
+    delete_account(user, body.into_inner(), &state).await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn delete_user_sessions(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<String>,
+) -> Result<HttpResponse, AppError> {
+    let user = req
+        .extensions()
+        .get::<String>()
+        .cloned()
+        .ok_or(AppError::Unauthorized)?;
+
+    revoke_user_sessions(user, path.into_inner(), &state).await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub fn client_info(req: &HttpRequest, client_name: Option<String>) -> ClientInfo {
+    let user_agent = req
+        .headers()
+        .get(header::USER_AGENT)
+        .and_then(|ua| ua.to_str().ok())
+        .map(|ua| ua.chars().take(256).collect());
+
+    ClientInfo {
+        client_name: client_name.map(|name| name.trim().chars().take(64).collect()),
+        user_agent,
+        ip: client_ip(req).map(|ip| ip.to_string()),
+    }
+}
+
+// Behind a trusted proxy the client is the right-most forwarded address
+// that is not a proxy itself. Anything further left was sent by the client
+// and cannot be trusted.
+fn client_ip(req: &HttpRequest) -> Option<IpAddr> {
+    let peer = req.peer_addr()?.ip();
+    let Some(state) = req.app_data::<web::Data<AppState>>() else {
+        return Some(peer);
+    };
+    if !state.trusted_proxies.contains(&peer) {
+        return Some(peer);
+    }
+
+    let values = |name| {
+        req.headers()
+            .get_all(name)
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+    };
+    let mut chain: Vec<String> = values(header::FORWARDED)
+        .iter()
+        .filter_map(|element| {
+            element.split(';').find_map(|pair| {
+                let (key, value) = pair.trim().split_once('=')?;
+                key.eq_ignore_ascii_case("for").then(|| value.to_string())
+            })
+        })
+        .collect();
+    if chain.is_empty() {
+        chain = values(header::HeaderName::from_static("x-forwarded-for"));
+    }
+
+    for entry in chain.iter().rev() {
+        let Some(ip) = parse_forwarded_ip(entry) else {
+            break;
+        };
+        if !state.trusted_proxies.contains(&ip) {
+            return Some(ip);
+        }
+    }
+    Some(peer)
+}
+
+// Accepts "1.2.3.4", "1.2.3.4:80", "::1", "[::1]:80" and their quoted forms.
+fn parse_forwarded_ip(entry: &str) -> Option<IpAddr> {
+    let entry = entry.trim().trim_matches('"');
+    if let Ok(ip) = entry.parse() {
+        return Some(ip);
+    }
+    if let Some(rest) = entry.strip_prefix('[') {
+        return rest.split_once(']')?.0.parse().ok();
+    }
+    entry.parse::<SocketAddr>().ok().map(|addr| addr.ip())
+}
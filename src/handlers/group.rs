@@ -0,0 +1,108 @@
+use actix_web::{web, HttpMessage, HttpRequest, HttpResponse};
+
+use crate::error::AppError;
+use crate::inputs::group::GroupCreate;
+use crate::services::group::{
+    add_audiobook, add_member, create_group, delete_group, list_groups, remove_audiobook,
+    remove_member,
+};
+use crate::state::AppState;
+
+pub async fn get_groups(state: web::Data<AppState>) -> Result<HttpResponse, AppError> {
+    let groups = list_groups(&state).await?;
+    Ok(HttpResponse::Ok().json(groups))
+}
+
+pub async fn post_group(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    body: web::Json<GroupCreate>,
+) -> Result<HttpResponse, AppError> {
+    let user = req
+        .extensions()
+        .get::<String>()
+        .cloned()
+        .ok_or(AppError::Unauthorized)?;
+
+    let group = create_group(user, body.into_inner(), &state).await?;
+    Ok(HttpResponse::Ok().json(group))
+}
+
+pub async fn delete_group_by_name(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<String>,
+) -> Result<HttpResponse, AppError> {
+    let user = req
+        .extensions()
+        .get::<String>()
+        .cloned()
+        .ok_or(AppError::Unauthorized)?;
+
+    delete_group(user, path.into_inner(), &state).await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn put_group_member(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+) -> Result<HttpResponse, AppError> {
+    let user = req
+        .extensions()
+        .get::<String>()
+        .cloned()
+        .ok_or(AppError::Unauthorized)?;
+
+    let (name, username) = path.into_inner();
+    let group = add_member(user, name, username, &state).await?;
+    Ok(HttpResponse::Ok().json(group))
+}
+
+pub async fn delete_group_member(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+) -> Result<HttpResponse, AppError> {
+    let user = req
+        .extensions()
+        .get::<String>()
+        .cloned()
+        .ok_or(AppError::Unauthorized)?;
+
+    let (name, username) = path.into_inner();
+    let group = remove_member(user, name, username, &state).await?;
+    Ok(HttpResponse::Ok().json(group))
+}
+
+pub async fn put_group_audiobook(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+) -> Result<HttpResponse, AppError> {
+    let user = req
+        .extensions()
+        .get::<String>()
+        .cloned()
+        .ok_or(AppError::Unauthorized)?;
+
+    let (name, hash) = path.into_inner();
+    let group = add_audiobook(user, name, hash, &state).await?;
+    Ok(HttpResponse::Ok().json(group))
+}
+
+pub async fn delete_group_audiobook(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+) -> Result<HttpResponse, AppError> {
+    let user = req
+        .extensions()
+        .get::<String>()
+        .cloned()
+        .ok_or(AppError::Unauthorized)?;
+
+    let (name, hash) = path.into_inner();
+    let group = remove_audiobook(user, name, hash, &state).await?;
+    Ok(HttpResponse::Ok().json(group))
+}
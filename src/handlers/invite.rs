@@ -0,0 +1,41 @@
+use actix_web::{web, HttpMessage, HttpRequest, HttpResponse};
+
+use crate::error::AppError;
+use crate::inputs::invite::InviteCreate;
+use crate::services::invite::{create_invite, delete_invite, list_invites};
+use crate::state::AppState;
+
+pub async fn get_invites(state: web::Data<AppState>) -> Result<HttpResponse, AppError> {
+    let invites = list_invites(&state).await?;
+    Ok(HttpResponse::Ok().json(invites))
+}
+
+pub async fn post_invite(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    body: web::Json<InviteCreate>,
+) -> Result<HttpResponse, AppError> {
+    let user = req
+        .extensions()
+        .get::<String>()
+        .cloned()
+        .ok_or(AppError::Unauthorized)?;
+
+    let invite = create_invite(user, body.into_inner(), &state).await?;
+    Ok(HttpResponse::Ok().json(invite))
+}
+
+pub async fn delete_invite_by_id(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<i64>,
+) -> Result<HttpResponse, AppError> {
+    let user = req
+        .extensions()
+        .get::<String>()
+        .cloned()
+        .ok_or(AppError::Unauthorized)?;
+
+    delete_invite(user, path.into_inner(), &state).await?;
+    Ok(HttpResponse::Ok().finish())
+}
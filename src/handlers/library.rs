@@ -1,15 +1,91 @@
-use actix_web::{web, HttpResponse};
+use actix_web::{web, HttpMessage, HttpRequest, HttpResponse};
 
 use crate::error::AppError;
-use crate::services::library::{cleanup, scan_library};
+use crate::services::library::{
+    cleanup, get_report_job, get_scan_job, list_libraries, purge_missing, request_report,
+    request_scan,
+};
+use crate::services::scheduler::list_tasks;
 use crate::state::AppState;
 
-pub async fn put_library_scan(state: web::Data<AppState>) -> Result<HttpResponse, AppError> {
-    scan_library(&state).await?;
-    Ok(HttpResponse::Ok().finish())
+pub async fn get_libraries(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+) -> Result<HttpResponse, AppError> {
+    let user = req
+        .extensions()
+        .get::<String>()
+        .cloned()
+        .ok_or(AppError::Unauthorized)?;
+
+    let libraries = list_libraries(user, &state).await?;
+    Ok(HttpResponse::Ok().json(libraries))
+}
+
+pub async fn put_library_scan(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+) -> Result<HttpResponse, AppError> {
+    let user = req
+        .extensions()
+        .get::<String>()
+        .cloned()
+        .ok_or(AppError::Unauthorized)?;
+
+    let job = request_scan(user, &state).await?;
+    Ok(HttpResponse::Accepted().json(job))
+}
+
+pub async fn get_library_scan(
+    state: web::Data<AppState>,
+    path: web::Path<u64>,
+) -> Result<HttpResponse, AppError> {
+    let job = get_scan_job(path.into_inner(), &state)?;
+    Ok(HttpResponse::Ok().json(job))
+}
+
+pub async fn put_library_report(state: web::Data<AppState>) -> Result<HttpResponse, AppError> {
+    let job = request_report(&state)?;
+    Ok(HttpResponse::Accepted().json(job))
 }
 
-pub async fn put_library_cleanup(state: web::Data<AppState>) -> Result<HttpResponse, AppError> {
-    cleanup(&state).await?;
+pub async fn get_library_report(
+    state: web::Data<AppState>,
+    path: web::Path<u64>,
+) -> Result<HttpResponse, AppError> {
+    let job = get_report_job(path.into_inner(), &state)?;
+    Ok(HttpResponse::Ok().json(job))
+}
+
+pub async fn delete_library_missing(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+) -> Result<HttpResponse, AppError> {
+    let user = req
+        .extensions()
+        .get::<String>()
+        .cloned()
+        .ok_or(AppError::Unauthorized)?;
+
+    let result = purge_missing(user, &state).await?;
+    Ok(HttpResponse::Ok().json(result))
+}
+
+pub async fn put_library_cleanup(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+) -> Result<HttpResponse, AppError> {
+    let user = req
+        .extensions()
+        .get::<String>()
+        .cloned()
+        .ok_or(AppError::Unauthorized)?;
+
+    cleanup(user, &state).await?;
     Ok(HttpResponse::Ok().finish())
 }
+
+pub async fn get_library_schedule(state: web::Data<AppState>) -> Result<HttpResponse, AppError> {
+    let tasks = list_tasks(&state);
+    Ok(HttpResponse::Ok().json(tasks))
+}
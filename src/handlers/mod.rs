@@ -1,4 +1,10 @@
+pub mod api_key;
 pub mod audiobook;
+pub mod audit;
 pub mod auth;
+pub mod group;
+pub mod invite;
 pub mod library;
+pub mod oidc;
 pub mod position;
+pub mod user;
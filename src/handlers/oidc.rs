@@ -0,0 +1,24 @@
+use actix_web::{http::header, web, HttpRequest, HttpResponse};
+
+use crate::error::AppError;
+use crate::handlers::auth::client_info;
+use crate::inputs::oidc::OidcCallback;
+use crate::services::oidc::{begin_login, finish_login};
+use crate::state::AppState;
+
+pub async fn get_oidc_login(state: web::Data<AppState>) -> Result<HttpResponse, AppError> {
+    let url = begin_login(&state).await?;
+    Ok(HttpResponse::Found()
+        .insert_header((header::LOCATION, url))
+        .finish())
+}
+
+pub async fn get_oidc_callback(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    query: web::Query<OidcCallback>,
+) -> Result<HttpResponse, AppError> {
+    let client = client_info(&req, None);
+    let token = finish_login(query.into_inner(), client, &state).await?;
+    Ok(HttpResponse::Ok().json(token))
+}
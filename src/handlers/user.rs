@@ -0,0 +1,84 @@
+use actix_web::{web, HttpMessage, HttpRequest, HttpResponse};
+
+use crate::error::AppError;
+use crate::inputs::user::{AdminGrant, PasswordReset, UserRename};
+use crate::services::user::{
+    delete_user, get_user, list_users, rename_user, reset_password, set_admin,
+};
+use crate::state::AppState;
+
+pub async fn get_users(state: web::Data<AppState>) -> Result<HttpResponse, AppError> {
+    let users = list_users(&state).await?;
+    Ok(HttpResponse::Ok().json(users))
+}
+
+pub async fn get_user_by_name(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, AppError> {
+    let user = get_user(path.into_inner(), &state).await?;
+    Ok(HttpResponse::Ok().json(user))
+}
+
+pub async fn delete_user_by_name(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<String>,
+) -> Result<HttpResponse, AppError> {
+    let user = req
+        .extensions()
+        .get::<String>()
+        .cloned()
+        .ok_or(AppError::Unauthorized)?;
+
+    delete_user(user, path.into_inner(), &state).await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn put_user_password(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<String>,
+    body: web::Json<PasswordReset>,
+) -> Result<HttpResponse, AppError> {
+    let user = req
+        .extensions()
+        .get::<String>()
+        .cloned()
+        .ok_or(AppError::Unauthorized)?;
+
+    reset_password(user, path.into_inner(), body.into_inner(), &state).await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn put_user_username(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<String>,
+    body: web::Json<UserRename>,
+) -> Result<HttpResponse, AppError> {
+    let actor = req
+        .extensions()
+        .get::<String>()
+        .cloned()
+        .ok_or(AppError::Unauthorized)?;
+
+    let user = rename_user(actor, path.into_inner(), body.into_inner(), &state).await?;
+    Ok(HttpResponse::Ok().json(user))
+}
+
+pub async fn put_user_admin(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<String>,
+    body: web::Json<AdminGrant>,
+) -> Result<HttpResponse, AppError> {
+    let actor = req
+        .extensions()
+        .get::<String>()
+        .cloned()
+        .ok_or(AppError::Unauthorized)?;
+
+    let user = set_admin(actor, path.into_inner(), body.into_inner(), &state).await?;
+    Ok(HttpResponse::Ok().json(user))
+}
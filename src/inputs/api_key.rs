@@ -0,0 +1,8 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+pub struct ApiKeyCreate {
+    pub name: String,
+    pub scope: Option<String>,
+    pub expires_in_days: Option<u64>,
+}
@@ -4,15 +4,37 @@ use serde::Deserialize;
 pub struct RegularLogin {
     pub username: String,
     pub password: String,
+    pub client_name: Option<String>,
 }
 
 #[derive(Deserialize, Debug)]
-pub struct AdminLogin {
+pub struct RegularRegister {
+    pub username: String,
     pub password: String,
+    pub invite: Option<String>,
+    pub client_name: Option<String>,
 }
 
 #[derive(Deserialize, Debug)]
-pub struct RegularRegister {
-    pub username: String,
+pub struct TokenRefresh {
+    pub refresh_token: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PasswordChange {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct AccountDeletion {
     pub password: String,
 }
+
+// Where a new session comes from, taken from the request by the handler.
+#[derive(Debug, Default)]
+pub struct ClientInfo {
+    pub client_name: Option<String>,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+}
@@ -0,0 +1,7 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+pub struct InviteCreate {
+    pub max_uses: Option<i64>,
+    pub expires_in_hours: Option<u64>,
+}
@@ -1,2 +1,9 @@
+pub mod api_key;
+pub mod audiobook;
+pub mod audit;
 pub mod auth;
+pub mod group;
+pub mod invite;
+pub mod oidc;
 pub mod position;
+pub mod user;
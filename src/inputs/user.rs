@@ -0,0 +1,16 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+pub struct PasswordReset {
+    pub password: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct UserRename {
+    pub username: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct AdminGrant {
+    pub is_admin: bool,
+}
@@ -14,16 +14,39 @@ use actix_web::middleware::from_fn;
 use actix_web::{web, App, HttpServer};
 use config::Config;
 use error::AppError;
+use handlers::api_key::{delete_api_key, get_api_keys, post_api_key};
 use handlers::audiobook::{get_audiobook, get_audiobook_download, get_audiobooks};
-use handlers::auth::{post_auth_admin, post_auth_login, post_auth_register};
-use handlers::library::{put_library_cleanup, put_library_scan};
+use handlers::audit::get_audit_events;
+use handlers::auth::{
+    delete_auth_account, delete_auth_session, delete_user_sessions, get_auth_sessions,
+    post_auth_login, post_auth_logout, post_auth_logout_all, post_auth_refresh,
+    post_auth_register, put_auth_password,
+};
+use handlers::group::{
+    delete_group_audiobook, delete_group_by_name, delete_group_member, get_groups, post_group,
+    put_group_audiobook, put_group_member,
+};
+use handlers::invite::{delete_invite_by_id, get_invites, post_invite};
+use handlers::library::{
+    delete_library_missing, get_libraries, get_library_report, get_library_schedule,
+    get_library_scan, put_library_cleanup, put_library_report, put_library_scan,
+};
+use handlers::oidc::{get_oidc_callback, get_oidc_login};
 use handlers::position::{get_position, put_position};
-use middlewares::auth::{admin_auth, standard_auth};
+use handlers::user::{
+    delete_user_by_name, get_user_by_name, get_users, put_user_admin, put_user_password,
+    put_user_username,
+};
+use middlewares::auth::{admin_auth, optional_auth, standard_auth};
 use middlewares::logging::log_request;
 use repo::audiobook as audiobook_repo;
+use services::auth::{bootstrap_admin, prune_sessions};
 use services::library::{build_archive, scan_library};
+use services::scheduler::spawn_scheduler;
+use services::watcher::spawn_watcher;
 use state::AppState;
 use std::path::PathBuf;
+use std::time::Duration;
 
 #[actix_web::main]
 async fn main() -> Result<(), AppError> {
@@ -33,6 +56,7 @@ async fn main() -> Result<(), AppError> {
 
     let config = Config::from_env()?;
     let state = AppState::new(&config).await?;
+    bootstrap_admin(&config, &state).await?;
 
     let worker_state = state.clone();
     tokio::spawn(async move {
@@ -76,11 +100,28 @@ async fn main() -> Result<(), AppError> {
         }
     });
 
+    let prune_state = state.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(3600));
+        loop {
+            interval.tick().await;
+            if let Err(e) = prune_sessions(&prune_state).await {
+                tracing::error!("session pruning failed: {}", e);
+            }
+        }
+    });
+
+    // A refused scan, e.g. for an unmounted library disk, keeps serving the
+    // books from the last scan instead of stopping the server.
     if let Err(e) = scan_library(&state).await {
         tracing::error!("initial library scan failed: {}", e);
-        std::process::exit(1);
     }
 
+    if config.watch_library {
+        spawn_watcher(state.clone(), Duration::from_secs(config.watch_debounce_seconds))?;
+    }
+    spawn_scheduler(&state, &config);
+
     tracing::info!("starting server at {}:{}", config.server_address, config.server_port);
 
     HttpServer::new(move || {
@@ -89,17 +130,133 @@ async fn main() -> Result<(), AppError> {
             .wrap(from_fn(log_request));
 
         app = app.service(web::resource("/auth/login").route(web::post().to(post_auth_login)));
-        app = app.service(web::resource("/auth/admin").route(web::post().to(post_auth_admin)));
-        if config.public_register {
-            app = app
-                .service(web::resource("/auth/register").route(web::post().to(post_auth_register)));
-        } else {
-            app = app.service(
-                web::resource("/auth/register")
-                    .wrap(from_fn(admin_auth))
-                    .route(web::post().to(post_auth_register)),
-            );
-        }
+        app = app.service(web::resource("/auth/refresh").route(web::post().to(post_auth_refresh)));
+        app = app.service(web::resource("/auth/oidc/login").route(web::get().to(get_oidc_login)));
+        app = app.service(
+            web::resource("/auth/oidc/callback").route(web::get().to(get_oidc_callback)),
+        );
+        app = app.service(
+            web::resource("/auth/register")
+                .wrap(from_fn(optional_auth))
+                .route(web::post().to(post_auth_register)),
+        );
+
+        app = app.service(
+            web::resource("/auth/logout")
+                .wrap(from_fn(standard_auth))
+                .route(web::post().to(post_auth_logout)),
+        );
+        app = app.service(
+            web::resource("/auth/logout-all")
+                .wrap(from_fn(standard_auth))
+                .route(web::post().to(post_auth_logout_all)),
+        );
+        app = app.service(
+            web::resource("/auth/sessions")
+                .wrap(from_fn(standard_auth))
+                .route(web::get().to(get_auth_sessions)),
+        );
+        app = app.service(
+            web::resource("/auth/sessions/{id}")
+                .wrap(from_fn(standard_auth))
+                .route(web::delete().to(delete_auth_session)),
+        );
+        app = app.service(
+            web::resource("/auth/password")
+                .wrap(from_fn(standard_auth))
+                .route(web::put().to(put_auth_password)),
+        );
+        app = app.service(
+            web::resource("/auth/account")
+                .wrap(from_fn(standard_auth))
+                .route(web::delete().to(delete_auth_account)),
+        );
+
+        app = app.service(
+            web::resource("/auth/keys")
+                .wrap(from_fn(standard_auth))
+                .route(web::get().to(get_api_keys))
+                .route(web::post().to(post_api_key)),
+        );
+        app = app.service(
+            web::resource("/auth/keys/{id}")
+                .wrap(from_fn(standard_auth))
+                .route(web::delete().to(delete_api_key)),
+        );
+
+        app = app.service(
+            web::resource("/invites")
+                .wrap(from_fn(admin_auth))
+                .route(web::get().to(get_invites))
+                .route(web::post().to(post_invite)),
+        );
+        app = app.service(
+            web::resource("/invites/{id}")
+                .wrap(from_fn(admin_auth))
+                .route(web::delete().to(delete_invite_by_id)),
+        );
+
+        app = app.service(
+            web::resource("/audit")
+                .wrap(from_fn(admin_auth))
+                .route(web::get().to(get_audit_events)),
+        );
+
+        app = app.service(
+            web::resource("/users")
+                .wrap(from_fn(admin_auth))
+                .route(web::get().to(get_users)),
+        );
+        app = app.service(
+            web::resource("/users/{username}")
+                .wrap(from_fn(admin_auth))
+                .route(web::get().to(get_user_by_name))
+                .route(web::delete().to(delete_user_by_name)),
+        );
+        app = app.service(
+            web::resource("/users/{username}/password")
+                .wrap(from_fn(admin_auth))
+                .route(web::put().to(put_user_password)),
+        );
+        app = app.service(
+            web::resource("/users/{username}/username")
+                .wrap(from_fn(admin_auth))
+                .route(web::put().to(put_user_username)),
+        );
+        app = app.service(
+            web::resource("/users/{username}/admin")
+                .wrap(from_fn(admin_auth))
+                .route(web::put().to(put_user_admin)),
+        );
+        app = app.service(
+            web::resource("/users/{username}/sessions")
+                .wrap(from_fn(admin_auth))
+                .route(web::delete().to(delete_user_sessions)),
+        );
+
+        app = app.service(
+            web::resource("/groups")
+                .wrap(from_fn(admin_auth))
+                .route(web::get().to(get_groups))
+                .route(web::post().to(post_group)),
+        );
+        app = app.service(
+            web::resource("/groups/{name}")
+                .wrap(from_fn(admin_auth))
+                .route(web::delete().to(delete_group_by_name)),
+        );
+        app = app.service(
+            web::resource("/groups/{name}/members/{username}")
+                .wrap(from_fn(admin_auth))
+                .route(web::put().to(put_group_member))
+                .route(web::delete().to(delete_group_member)),
+        );
+        app = app.service(
+            web::resource("/groups/{name}/audiobooks/{hash}")
+                .wrap(from_fn(admin_auth))
+                .route(web::put().to(put_group_audiobook))
+                .route(web::delete().to(delete_group_audiobook)),
+        );
 
         app = app.service(
             web::resource("/audiobooks")
@@ -124,16 +281,46 @@ async fn main() -> Result<(), AppError> {
                 .route(web::put().to(put_position)),
         );
 
+        app = app.service(
+            web::resource("/libraries")
+                .wrap(from_fn(standard_auth))
+                .route(web::get().to(get_libraries)),
+        );
         app = app.service(
             web::resource("/library/scan")
                 .wrap(from_fn(admin_auth))
                 .route(web::put().to(put_library_scan)),
         );
+        app = app.service(
+            web::resource("/library/scan/{id}")
+                .wrap(from_fn(admin_auth))
+                .route(web::get().to(get_library_scan)),
+        );
+        app = app.service(
+            web::resource("/library/report")
+                .wrap(from_fn(admin_auth))
+                .route(web::put().to(put_library_report)),
+        );
+        app = app.service(
+            web::resource("/library/report/{id}")
+                .wrap(from_fn(admin_auth))
+                .route(web::get().to(get_library_report)),
+        );
+        app = app.service(
+            web::resource("/library/missing")
+                .wrap(from_fn(admin_auth))
+                .route(web::delete().to(delete_library_missing)),
+        );
         app = app.service(
             web::resource("/library/cleanup")
                 .wrap(from_fn(admin_auth))
                 .route(web::put().to(put_library_cleanup)),
         );
+        app = app.service(
+            web::resource("/library/schedule")
+                .wrap(from_fn(admin_auth))
+                .route(web::get().to(get_library_schedule)),
+        );
 
         app
     })
@@ -1,15 +1,29 @@
 use actix_web::{
     dev::{ServiceRequest, ServiceResponse},
+    http::Method,
     middleware::Next,
     web::Data,
     Error, HttpMessage,
 };
-
+use chrono::Utc;
+
+use crate::models::api_key::{ApiKey, SCOPE_FULL, SCOPE_LIBRARY_ADMIN, SCOPE_READ_ONLY};
+use crate::models::session::Session;
+use crate::repo::{api_key as api_key_repo, session as session_repo, user as user_repo};
+use crate::services::api_key::API_KEY_PREFIX;
+use crate::services::auth::{add_ttl, hash_token, provision_user};
+use crate::services::validation::check_username;
 use crate::state::AppState;
 
+enum Credential {
+    Session(Session),
+    ApiKey(ApiKey),
+    Proxy,
+}
+
 enum UserType {
-    Admin,
-    Regular(String),
+    Admin(String, Credential),
+    Regular(String, Credential),
 }
 
 enum AuthError {
@@ -21,15 +35,17 @@ pub async fn standard_auth(
     req: ServiceRequest,
     next: Next<impl actix_web::body::MessageBody>,
 ) -> Result<ServiceResponse<impl actix_web::body::MessageBody>, Error> {
-    let token = extract_token(&req)?;
-
-    match validate_token(&req, token) {
-        Ok(UserType::Admin) => {
-            req.extensions_mut().insert("admin".to_string());
-            next.call(req).await
-        }
-        Ok(UserType::Regular(username)) => {
-            req.extensions_mut().insert(username);
+    let identity = match proxy_username(&req) {
+        Some(username) => validate_proxy_user(&req, username).await,
+        None => validate_token(&req, extract_token(&req)?).await,
+    };
+
+    match identity {
+        Ok(UserType::Admin(username, credential)) | Ok(UserType::Regular(username, credential)) => {
+            if !scope_allows(&req, &credential, false) {
+                return Err(actix_web::error::ErrorForbidden("api key scope does not allow this"));
+            }
+            insert_identity(&req, username, credential);
             next.call(req).await
         }
         Err(AuthError::Unauthorized) => Err(actix_web::error::ErrorUnauthorized("invalid token")),
@@ -41,20 +57,39 @@ pub async fn admin_auth(
     req: ServiceRequest,
     next: Next<impl actix_web::body::MessageBody>,
 ) -> Result<ServiceResponse<impl actix_web::body::MessageBody>, Error> {
-    let token = extract_token(&req)?;
-
-    match validate_token(&req, token) {
-        Ok(UserType::Admin) => {
-            req.extensions_mut().insert("admin".to_string());
+    let identity = match proxy_username(&req) {
+        Some(username) => validate_proxy_user(&req, username).await,
+        None => validate_token(&req, extract_token(&req)?).await,
+    };
+
+    match identity {
+        Ok(UserType::Admin(username, credential)) => {
+            if !scope_allows(&req, &credential, true) {
+                return Err(actix_web::error::ErrorForbidden("api key scope does not allow this"));
+            }
+            insert_identity(&req, username, credential);
             next.call(req).await
         }
-        Ok(UserType::Regular(_)) | Err(AuthError::Unauthorized) => {
+        Ok(UserType::Regular(..)) | Err(AuthError::Unauthorized) => {
             Err(actix_web::error::ErrorUnauthorized("invalid token"))
         }
         Err(AuthError::InternalError(e)) => Err(actix_web::error::ErrorInternalServerError(e)),
     }
 }
 
+pub async fn optional_auth(
+    req: ServiceRequest,
+    next: Next<impl actix_web::body::MessageBody + 'static>,
+) -> Result<ServiceResponse<impl actix_web::body::MessageBody>, Error> {
+    if req.headers().contains_key("Authorization") || proxy_username(&req).is_some() {
+        standard_auth(req, next)
+            .await
+            .map(ServiceResponse::map_into_left_body)
+    } else {
+        next.call(req).await.map(ServiceResponse::map_into_right_body)
+    }
+}
+
 fn extract_token(req: &ServiceRequest) -> Result<String, Error> {
     let auth_header = req
         .headers()
@@ -71,33 +106,140 @@ fn extract_token(req: &ServiceRequest) -> Result<String, Error> {
         .map(|s| s.to_string())
 }
 
-fn validate_token(req: &ServiceRequest, token: String) -> Result<UserType, AuthError> {
+// The proxy header is only trusted on requests without a bearer token that
+// come straight from one of the configured proxies.
+fn proxy_username(req: &ServiceRequest) -> Option<String> {
+    let state = req.app_data::<Data<AppState>>()?;
+    let header = state.auth_proxy_header.as_ref()?;
+    if req.headers().contains_key("Authorization") {
+        return None;
+    }
+
+    let peer = req.peer_addr()?.ip();
+    if !state.trusted_proxies.contains(&peer) {
+        return None;
+    }
+
+    req.headers()
+        .get(header)?
+        .to_str()
+        .ok()
+        .map(str::trim)
+        .filter(|username| !username.is_empty())
+        .map(String::from)
+}
+
+fn insert_identity(req: &ServiceRequest, username: String, credential: Credential) {
+    req.extensions_mut().insert(username);
+    match credential {
+        Credential::Session(session) => {
+            req.extensions_mut().insert(session);
+        }
+        Credential::ApiKey(api_key) => {
+            req.extensions_mut().insert(api_key);
+        }
+        Credential::Proxy => {}
+    }
+}
+
+fn scope_allows(req: &ServiceRequest, credential: &Credential, admin: bool) -> bool {
+    let Credential::ApiKey(api_key) = credential else {
+        return true;
+    };
+
+    match api_key.scope.as_str() {
+        SCOPE_FULL => true,
+        SCOPE_READ_ONLY => !admin && matches!(*req.method(), Method::GET | Method::HEAD),
+        SCOPE_LIBRARY_ADMIN => admin && req.path().starts_with("/library"),
+        _ => false,
+    }
+}
+
+async fn validate_proxy_user(req: &ServiceRequest, username: String) -> Result<UserType, AuthError> {
     let state = req
         .app_data::<Data<AppState>>()
+        .cloned()
         .ok_or(AuthError::InternalError("AppState not found".to_string()))?;
 
-    let admin_tokens = state
-        .admin_tokens
-        .lock()
-        .map_err(|_| AuthError::InternalError("lock poisoned".to_string()))?;
+    let user = user_repo::find_by_folded_username(&state.db, &username)
+        .await
+        .map_err(|e| AuthError::InternalError(e.to_string()))?;
+
+    let user = match user {
+        Some(user) => user,
+        None => {
+            let mut errors = Vec::new();
+            let username = check_username("username", &username, &state, &mut errors);
+            if !errors.is_empty() {
+                tracing::warn!("proxy sent invalid username {:?}", username);
+                return Err(AuthError::Unauthorized);
+            }
+
+            provision_user(&state, &username, "proxy authentication")
+                .await
+                .map_err(|e| AuthError::InternalError(e.to_string()))?;
+
+            user_repo::find_by_username(&state.db, &username)
+                .await
+                .map_err(|e| AuthError::InternalError(e.to_string()))?
+                .ok_or(AuthError::Unauthorized)?
+        }
+    };
 
-    if admin_tokens
-        .iter()
-        .any(|(t, created)| t == &token && created.elapsed() < state.token_ttl)
-    {
-        return Ok(UserType::Admin);
+    if user.is_admin {
+        Ok(UserType::Admin(user.username, Credential::Proxy))
+    } else {
+        Ok(UserType::Regular(user.username, Credential::Proxy))
     }
+}
 
-    let regular_tokens = state
-        .regular_tokens
-        .lock()
-        .map_err(|_| AuthError::InternalError("lock poisoned".to_string()))?;
+async fn validate_token(req: &ServiceRequest, token: String) -> Result<UserType, AuthError> {
+    let state = req
+        .app_data::<Data<AppState>>()
+        .cloned()
+        .ok_or(AuthError::InternalError("AppState not found".to_string()))?;
 
-    if let Some((username, created)) = regular_tokens.get(&token) {
-        if created.elapsed() < state.token_ttl {
-            return Ok(UserType::Regular(username.clone()));
-        }
+    let now = Utc::now().naive_utc();
+    let (username, credential) = if token.starts_with(API_KEY_PREFIX) {
+        let api_key = api_key_repo::find_valid(&state.db, &hash_token(&token), now)
+            .await
+            .map_err(|e| AuthError::InternalError(e.to_string()))?
+            .ok_or(AuthError::Unauthorized)?;
+
+        api_key_repo::touch(&state.db, api_key.id, now)
+            .await
+            .map_err(|e| AuthError::InternalError(e.to_string()))?;
+
+        (api_key.username.clone(), Credential::ApiKey(api_key))
+    } else {
+        let session = session_repo::find_valid(&state.db, &hash_token(&token), now)
+            .await
+            .map_err(|e| AuthError::InternalError(e.to_string()))?
+            .ok_or(AuthError::Unauthorized)?;
+
+        let touched = if state.sliding_expiry {
+            match add_ttl(now, state.token_ttl) {
+                Ok(expires_at) => {
+                    session_repo::extend(&state.db, session.id, now, expires_at).await
+                }
+                Err(e) => Err(e),
+            }
+        } else {
+            session_repo::touch(&state.db, session.id, now).await
+        };
+        touched.map_err(|e| AuthError::InternalError(e.to_string()))?;
+
+        (session.username.clone(), Credential::Session(session))
+    };
+
+    let user = user_repo::find_by_username(&state.db, &username)
+        .await
+        .map_err(|e| AuthError::InternalError(e.to_string()))?
+        .ok_or(AuthError::Unauthorized)?;
+
+    if user.is_admin {
+        Ok(UserType::Admin(user.username, credential))
+    } else {
+        Ok(UserType::Regular(user.username, credential))
     }
-
-    Err(AuthError::Unauthorized)
 }
@@ -0,0 +1,19 @@
+use chrono::NaiveDateTime;
+use sqlx::FromRow;
+
+pub const SCOPE_FULL: &str = "full";
+pub const SCOPE_READ_ONLY: &str = "read-only";
+pub const SCOPE_LIBRARY_ADMIN: &str = "library-admin";
+
+#[derive(Debug, Clone, FromRow)]
+#[allow(dead_code)]
+pub struct ApiKey {
+    pub id: i64, // primary key
+    pub username: String,
+    pub name: String,
+    pub key_hash: String,
+    pub scope: String,
+    pub created_at: NaiveDateTime,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub expires_at: Option<NaiveDateTime>,
+}
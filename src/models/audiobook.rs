@@ -1,3 +1,4 @@
+use chrono::NaiveDateTime;
 use sqlx::FromRow;
 
 #[derive(Debug, FromRow)]
@@ -15,4 +16,7 @@ pub struct Audiobook {
     pub final_chapter_position: i64,
     pub cover: Option<String>,
     pub archive_ready: bool,
+    pub library: String,
+    pub missing_since: Option<NaiveDateTime>,
+    pub missing_reason: Option<String>,
 }
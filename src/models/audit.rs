@@ -0,0 +1,39 @@
+use chrono::NaiveDateTime;
+use sqlx::FromRow;
+
+pub const ACTION_LOGIN: &str = "login";
+pub const ACTION_LOGIN_FAILED: &str = "login.failed";
+pub const ACTION_REGISTER: &str = "register";
+pub const ACTION_PASSWORD_CHANGE: &str = "password.change";
+pub const ACTION_ACCOUNT_DELETE: &str = "account.delete";
+pub const ACTION_SESSION_REVOKE: &str = "session.revoke";
+pub const ACTION_API_KEY_CREATE: &str = "api_key.create";
+pub const ACTION_API_KEY_REVOKE: &str = "api_key.revoke";
+pub const ACTION_INVITE_CREATE: &str = "invite.create";
+pub const ACTION_INVITE_DELETE: &str = "invite.delete";
+pub const ACTION_USER_PROVISION: &str = "user.provision";
+pub const ACTION_USER_DELETE: &str = "user.delete";
+pub const ACTION_USER_PASSWORD_RESET: &str = "user.password_reset";
+pub const ACTION_USER_ADMIN: &str = "user.admin";
+pub const ACTION_USER_RENAME: &str = "user.rename";
+pub const ACTION_USER_SESSIONS_REVOKE: &str = "user.sessions_revoke";
+pub const ACTION_GROUP_CREATE: &str = "group.create";
+pub const ACTION_GROUP_DELETE: &str = "group.delete";
+pub const ACTION_GROUP_MEMBER_ADD: &str = "group.member_add";
+pub const ACTION_GROUP_MEMBER_REMOVE: &str = "group.member_remove";
+pub const ACTION_GROUP_AUDIOBOOK_ADD: &str = "group.audiobook_add";
+pub const ACTION_GROUP_AUDIOBOOK_REMOVE: &str = "group.audiobook_remove";
+pub const ACTION_LIBRARY_SCAN: &str = "library.scan";
+pub const ACTION_LIBRARY_CLEANUP: &str = "library.cleanup";
+pub const ACTION_LIBRARY_PURGE: &str = "library.purge";
+
+#[derive(Debug, FromRow)]
+#[allow(dead_code)]
+pub struct AuditEvent {
+    pub id: i64, // primary key
+    pub created_at: NaiveDateTime,
+    pub actor: Option<String>,
+    pub action: String,
+    pub target: Option<String>,
+    pub details: Option<String>,
+}
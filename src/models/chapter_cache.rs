@@ -0,0 +1,10 @@
+use sqlx::FromRow;
+
+// Probed duration of a chapter file, valid as long as size and mtime match.
+#[derive(Debug, Clone, PartialEq, FromRow)]
+pub struct CachedChapter {
+    pub path: String, // primary key
+    pub size: i64,
+    pub mtime_ns: i64,
+    pub duration_us: i64,
+}
@@ -0,0 +1,9 @@
+use chrono::NaiveDateTime;
+use sqlx::FromRow;
+
+#[derive(Debug, FromRow)]
+#[allow(dead_code)]
+pub struct Group {
+    pub name: String, // primary key
+    pub created_at: NaiveDateTime,
+}
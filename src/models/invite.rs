@@ -0,0 +1,14 @@
+use chrono::NaiveDateTime;
+use sqlx::FromRow;
+
+#[derive(Debug, FromRow)]
+#[allow(dead_code)]
+pub struct Invite {
+    pub id: i64, // primary key
+    pub code_hash: String,
+    pub created_by: String,
+    pub max_uses: i64,
+    pub uses: i64,
+    pub created_at: NaiveDateTime,
+    pub expires_at: Option<NaiveDateTime>,
+}
@@ -0,0 +1,20 @@
+use chrono::NaiveDateTime;
+use serde::Deserialize;
+use sqlx::FromRow;
+
+#[derive(Debug, FromRow)]
+#[allow(dead_code)]
+pub struct OidcIdentity {
+    pub issuer: String,  // primary key
+    pub subject: String, // primary key
+    pub username: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProviderMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
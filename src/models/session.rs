@@ -0,0 +1,18 @@
+use chrono::NaiveDateTime;
+use sqlx::FromRow;
+
+#[derive(Debug, Clone, FromRow)]
+#[allow(dead_code)]
+pub struct Session {
+    pub id: i64, // primary key
+    pub token_hash: String,
+    pub username: String,
+    pub created_at: NaiveDateTime,
+    pub last_used_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+    pub refresh_token_hash: Option<String>,
+    pub refresh_expires_at: Option<NaiveDateTime>,
+    pub client_name: Option<String>,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+}
@@ -1,8 +1,19 @@
 use sqlx::FromRow;
+use unicode_normalization::UnicodeNormalization;
 
 #[derive(Debug, FromRow)]
 #[allow(dead_code)]
 pub struct User {
     pub username: String, // primary key
     pub password_hash: String,
+    pub is_admin: bool,
+}
+
+pub fn normalize_username(username: &str) -> String {
+    username.nfc().collect()
+}
+
+// Key used to compare usernames case-insensitively.
+pub fn fold_username(username: &str) -> String {
+    normalize_username(username).to_lowercase()
 }
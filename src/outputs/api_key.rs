@@ -0,0 +1,20 @@
+use serde::Serialize;
+
+#[derive(Serialize, Debug)]
+pub struct ApiKeyInfo {
+    pub id: i64,
+    pub name: String,
+    pub scope: String,
+    pub created_at: i64,
+    pub last_used_at: Option<i64>,
+    pub expires_at: Option<i64>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ApiKeyCreated {
+    pub id: i64,
+    pub name: String,
+    pub scope: String,
+    pub key: String,
+    pub expires_at: Option<i64>,
+}
@@ -9,6 +9,8 @@ pub struct AudiobookShort {
     pub genres: Vec<String>,
     pub duration: i64,
     pub archive_ready: bool,
+    pub library: String,
+    pub missing_since: Option<i64>,
 }
 
 #[derive(Serialize, Debug)]
@@ -23,4 +25,7 @@ pub struct AudiobookLong {
     pub size: i64,
     pub cover: Option<String>,
     pub archive_ready: bool,
+    pub library: String,
+    pub missing_since: Option<i64>,
+    pub missing_reason: Option<String>,
 }
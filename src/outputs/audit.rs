@@ -0,0 +1,11 @@
+use serde::Serialize;
+
+#[derive(Serialize, Debug)]
+pub struct AuditEventInfo {
+    pub id: i64,
+    pub created_at: i64,
+    pub actor: Option<String>,
+    pub action: String,
+    pub target: Option<String>,
+    pub details: Option<String>,
+}
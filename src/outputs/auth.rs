@@ -3,4 +3,17 @@ use serde::Serialize;
 #[derive(Serialize, Debug)]
 pub struct AuthToken {
     pub token: String,
+    pub refresh_token: String,
+    pub expires_in: u64,
+}
+
+#[derive(Serialize, Debug)]
+pub struct SessionInfo {
+    pub id: i64,
+    pub client_name: Option<String>,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    pub created_at: i64,
+    pub last_used_at: i64,
+    pub current: bool,
 }
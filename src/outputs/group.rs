@@ -0,0 +1,8 @@
+use serde::Serialize;
+
+#[derive(Serialize, Debug)]
+pub struct GroupInfo {
+    pub name: String,
+    pub members: Vec<String>,
+    pub audiobooks: Vec<String>,
+}
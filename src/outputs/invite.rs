@@ -0,0 +1,19 @@
+use serde::Serialize;
+
+#[derive(Serialize, Debug)]
+pub struct InviteInfo {
+    pub id: i64,
+    pub created_by: String,
+    pub max_uses: i64,
+    pub uses: i64,
+    pub created_at: i64,
+    pub expires_at: Option<i64>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct InviteCreated {
+    pub id: i64,
+    pub code: String,
+    pub max_uses: i64,
+    pub expires_at: Option<i64>,
+}
@@ -0,0 +1,90 @@
+use serde::Serialize;
+
+#[derive(Serialize, Debug)]
+pub struct LibraryInfo {
+    pub name: String,
+    pub audiobooks: usize,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ScanErrorInfo {
+    pub path: String,
+    pub message: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ScanJobInfo {
+    pub id: u64,
+    pub state: &'static str,
+    pub started_at: i64,
+    pub finished_at: Option<i64>,
+    pub books_total: usize,
+    pub books_processed: usize,
+    pub chapters_total: usize,
+    pub chapters_processed: usize,
+    pub errors: Vec<ScanErrorInfo>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct TaskRunInfo {
+    pub started_at: i64,
+    pub finished_at: i64,
+    pub success: bool,
+    pub message: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ScheduledTaskInfo {
+    pub task: &'static str,
+    pub interval_minutes: u64,
+    pub next_run_at: i64,
+    pub last_run: Option<TaskRunInfo>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct BookReport {
+    pub path: String,
+    pub library: String,
+    pub hash: Option<String>,
+    pub title: Option<String>,
+    pub action: &'static str,
+    pub problems: Vec<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct StaleBook {
+    pub hash: String,
+    pub title: String,
+    pub path: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct DuplicateHash {
+    pub hash: String,
+    pub paths: Vec<String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ScanReport {
+    pub error: Option<String>,
+    pub books: Vec<BookReport>,
+    pub missing: Vec<StaleBook>,
+    pub deleted: Vec<StaleBook>,
+    pub duplicates: Vec<DuplicateHash>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ReportJobInfo {
+    pub id: u64,
+    pub state: &'static str,
+    pub started_at: i64,
+    pub finished_at: Option<i64>,
+    pub report: Option<ScanReport>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct PurgeResult {
+    pub purged: usize,
+}
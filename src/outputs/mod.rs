@@ -1,3 +1,9 @@
+pub mod api_key;
 pub mod audiobook;
+pub mod audit;
 pub mod auth;
+pub mod group;
+pub mod invite;
+pub mod library;
 pub mod position;
+pub mod user;
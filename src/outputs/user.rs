@@ -0,0 +1,7 @@
+use serde::Serialize;
+
+#[derive(Serialize, Debug)]
+pub struct UserInfo {
+    pub username: String,
+    pub is_admin: bool,
+}
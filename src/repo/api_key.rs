@@ -0,0 +1,113 @@
+use crate::{error::AppError, models::api_key::ApiKey};
+use chrono::NaiveDateTime;
+use sqlx::SqlitePool;
+
+pub async fn find_by_username(db: &SqlitePool, username: &str) -> Result<Vec<ApiKey>, AppError> {
+    sqlx::query_as!(
+        ApiKey,
+        r#"SELECT
+            id as "id!",
+            username as "username!",
+            name as "name!",
+            key_hash as "key_hash!",
+            scope as "scope!",
+            created_at as "created_at!: NaiveDateTime",
+            last_used_at as "last_used_at: NaiveDateTime",
+            expires_at as "expires_at: NaiveDateTime"
+        FROM api_keys WHERE username = ? ORDER BY created_at"#,
+        username
+    )
+    .fetch_all(db)
+    .await
+    .map_err(AppError::from)
+}
+
+pub async fn find_valid(
+    db: &SqlitePool,
+    key_hash: &str,
+    now: NaiveDateTime,
+) -> Result<Option<ApiKey>, AppError> {
+    sqlx::query_as!(
+        ApiKey,
+        r#"SELECT
+            id as "id!",
+            username as "username!",
+            name as "name!",
+            key_hash as "key_hash!",
+            scope as "scope!",
+            created_at as "created_at!: NaiveDateTime",
+            last_used_at as "last_used_at: NaiveDateTime",
+            expires_at as "expires_at: NaiveDateTime"
+        FROM api_keys WHERE key_hash = ? AND (expires_at IS NULL OR expires_at > ?)"#,
+        key_hash,
+        now,
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(AppError::from)
+}
+
+pub async fn find_by_name(
+    db: &SqlitePool,
+    username: &str,
+    name: &str,
+) -> Result<Option<ApiKey>, AppError> {
+    sqlx::query_as!(
+        ApiKey,
+        r#"SELECT
+            id as "id!",
+            username as "username!",
+            name as "name!",
+            key_hash as "key_hash!",
+            scope as "scope!",
+            created_at as "created_at!: NaiveDateTime",
+            last_used_at as "last_used_at: NaiveDateTime",
+            expires_at as "expires_at: NaiveDateTime"
+        FROM api_keys WHERE username = ? AND name = ?"#,
+        username,
+        name,
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(AppError::from)
+}
+
+pub async fn create(db: &SqlitePool, api_key: &ApiKey) -> Result<i64, AppError> {
+    let result = sqlx::query!(
+        "INSERT INTO api_keys (username, name, key_hash, scope, created_at, expires_at) VALUES (?, ?, ?, ?, ?, ?)",
+        api_key.username,
+        api_key.name,
+        api_key.key_hash,
+        api_key.scope,
+        api_key.created_at,
+        api_key.expires_at,
+    )
+    .execute(db)
+    .await?;
+    Ok(result.last_insert_rowid())
+}
+
+pub async fn touch(db: &SqlitePool, id: i64, now: NaiveDateTime) -> Result<(), AppError> {
+    sqlx::query!("UPDATE api_keys SET last_used_at = ? WHERE id = ?", now, id)
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+pub async fn delete(db: &SqlitePool, id: i64, username: &str) -> Result<u64, AppError> {
+    let result = sqlx::query!(
+        "DELETE FROM api_keys WHERE id = ? AND username = ?",
+        id,
+        username,
+    )
+    .execute(db)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+pub async fn delete_by_username(db: &SqlitePool, username: &str) -> Result<u64, AppError> {
+    let result = sqlx::query!("DELETE FROM api_keys WHERE username = ?", username)
+        .execute(db)
+        .await?;
+    Ok(result.rows_affected())
+}
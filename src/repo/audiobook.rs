@@ -1,4 +1,5 @@
 use crate::{error::AppError, models::audiobook::Audiobook};
+use chrono::NaiveDateTime;
 use sqlx::SqlitePool;
 use std::collections::HashMap;
 
@@ -18,7 +19,10 @@ pub async fn find_all(db: &SqlitePool) -> Result<Vec<Audiobook>, AppError> {
             final_chapter_index as "final_chapter_index!",
             final_chapter_position as "final_chapter_position!",
             cover,
-            archive_ready as "archive_ready!: bool"
+            archive_ready as "archive_ready!: bool",
+            library as "library!",
+            missing_since as "missing_since: NaiveDateTime",
+            missing_reason
         FROM audiobooks"#
     )
     .fetch_all(db)
@@ -26,6 +30,44 @@ pub async fn find_all(db: &SqlitePool) -> Result<Vec<Audiobook>, AppError> {
     .map_err(AppError::from)
 }
 
+// Books in no group are visible to everyone, the others only to members of
+// one of their groups. Missing books are hidden.
+pub async fn find_visible(db: &SqlitePool, username: &str) -> Result<Vec<Audiobook>, AppError> {
+    sqlx::query_as!(
+        Audiobook,
+        r#"SELECT
+            hash as "hash!",
+            title as "title!",
+            author as "author!",
+            date as "date!: i32",
+            description as "description!",
+            genres as "genres!",
+            duration as "duration!",
+            size as "size!",
+            path as "path!",
+            final_chapter_index as "final_chapter_index!",
+            final_chapter_position as "final_chapter_position!",
+            cover,
+            archive_ready as "archive_ready!: bool",
+            library as "library!",
+            missing_since as "missing_since: NaiveDateTime",
+            missing_reason
+        FROM audiobooks
+        WHERE missing_since IS NULL AND (
+            NOT EXISTS (SELECT 1 FROM audiobook_groups ag WHERE ag.audiobook_hash = audiobooks.hash)
+            OR EXISTS (
+                SELECT 1 FROM audiobook_groups ag
+                JOIN group_members gm ON gm.group_name = ag.group_name
+                WHERE ag.audiobook_hash = audiobooks.hash AND gm.username = ?
+            )
+        )"#,
+        username
+    )
+    .fetch_all(db)
+    .await
+    .map_err(AppError::from)
+}
+
 pub async fn find_by_hash(db: &SqlitePool, hash: &str) -> Result<Option<Audiobook>, AppError> {
     sqlx::query_as!(
         Audiobook,
@@ -42,7 +84,10 @@ pub async fn find_by_hash(db: &SqlitePool, hash: &str) -> Result<Option<Audioboo
             final_chapter_index as "final_chapter_index!",
             final_chapter_position as "final_chapter_position!",
             cover,
-            archive_ready as "archive_ready!: bool"
+            archive_ready as "archive_ready!: bool",
+            library as "library!",
+            missing_since as "missing_since: NaiveDateTime",
+            missing_reason
         FROM audiobooks WHERE hash = ?"#,
         hash
     )
@@ -78,7 +123,7 @@ pub async fn find_path(db: &SqlitePool, hash: &str) -> Result<Option<String>, Ap
 
 pub async fn create(db: &SqlitePool, audiobook: &Audiobook) -> Result<(), AppError> {
     sqlx::query!(
-        "INSERT INTO audiobooks (hash, title, author, date, description, genres, duration, size, path, final_chapter_index, final_chapter_position, cover) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        "INSERT INTO audiobooks (hash, title, author, date, description, genres, duration, size, path, final_chapter_index, final_chapter_position, cover, library) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
         audiobook.hash,
         audiobook.title,
         audiobook.author,
@@ -91,6 +136,7 @@ pub async fn create(db: &SqlitePool, audiobook: &Audiobook) -> Result<(), AppErr
         audiobook.final_chapter_index,
         audiobook.final_chapter_position,
         audiobook.cover,
+        audiobook.library,
     )
     .execute(db)
     .await?;
@@ -99,7 +145,7 @@ pub async fn create(db: &SqlitePool, audiobook: &Audiobook) -> Result<(), AppErr
 
 pub async fn update(db: &SqlitePool, audiobook: &Audiobook) -> Result<(), AppError> {
     sqlx::query!(
-        "UPDATE audiobooks SET title = ?, author = ?, date = ?, description = ?, genres = ?, duration = ?, size = ?, path = ?, final_chapter_index = ?, final_chapter_position = ?, cover = ? WHERE hash = ?",
+        "UPDATE audiobooks SET title = ?, author = ?, date = ?, description = ?, genres = ?, duration = ?, size = ?, path = ?, final_chapter_index = ?, final_chapter_position = ?, cover = ?, library = ?, missing_since = NULL, missing_reason = NULL WHERE hash = ?",
         audiobook.title,
         audiobook.author,
         audiobook.date,
@@ -111,6 +157,7 @@ pub async fn update(db: &SqlitePool, audiobook: &Audiobook) -> Result<(), AppErr
         audiobook.final_chapter_index,
         audiobook.final_chapter_position,
         audiobook.cover,
+        audiobook.library,
         audiobook.hash,
     )
     .execute(db)
@@ -119,9 +166,92 @@ pub async fn update(db: &SqlitePool, audiobook: &Audiobook) -> Result<(), AppErr
 }
 
 pub async fn delete(db: &SqlitePool, hash: &str) -> Result<(), AppError> {
+    let mut tx = db.begin().await?;
+    sqlx::query!("DELETE FROM audiobook_aliases WHERE hash = ?", hash)
+        .execute(&mut *tx)
+        .await?;
     sqlx::query!("DELETE FROM audiobooks WHERE hash = ?", hash)
-        .execute(db)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+pub async fn find_alias(db: &SqlitePool, alias: &str) -> Result<Option<String>, AppError> {
+    sqlx::query_scalar!(
+        r#"SELECT hash as "hash!" FROM audiobook_aliases WHERE alias = ?"#,
+        alias
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(AppError::from)
+}
+
+// Moves a book with its positions and group assignments to a new hash. The old
+// hash, and every alias that pointed to it, becomes an alias of the new one.
+// Positions or groups that already exist under the new hash win.
+pub async fn change_hash(
+    db: &SqlitePool,
+    old: &str,
+    new: &str,
+    now: NaiveDateTime,
+) -> Result<(), AppError> {
+    let mut tx = db.begin().await?;
+    sqlx::query!("DELETE FROM audiobook_aliases WHERE alias = ?", new)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query!("UPDATE audiobook_aliases SET hash = ? WHERE hash = ?", new, old)
+        .execute(&mut *tx)
         .await?;
+    sqlx::query!(
+        "INSERT INTO audiobook_aliases (alias, hash, created_at) VALUES (?, ?, ?)",
+        old,
+        new,
+        now,
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!("UPDATE audiobooks SET hash = ? WHERE hash = ?", new, old)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query!(
+        "UPDATE OR IGNORE positions SET audiobook_hash = ? WHERE audiobook_hash = ?",
+        new,
+        old,
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!("DELETE FROM positions WHERE audiobook_hash = ?", old)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query!(
+        "UPDATE OR IGNORE audiobook_groups SET audiobook_hash = ? WHERE audiobook_hash = ?",
+        new,
+        old,
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!("DELETE FROM audiobook_groups WHERE audiobook_hash = ?", old)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+pub async fn mark_missing(
+    db: &SqlitePool,
+    hash: &str,
+    since: NaiveDateTime,
+    reason: &str,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        "UPDATE audiobooks SET missing_since = ?, missing_reason = ? WHERE hash = ?",
+        since,
+        reason,
+        hash,
+    )
+    .execute(db)
+    .await?;
     Ok(())
 }
 
@@ -0,0 +1,56 @@
+use crate::{error::AppError, models::audit::AuditEvent};
+use chrono::NaiveDateTime;
+use sqlx::SqlitePool;
+
+pub async fn find(
+    db: &SqlitePool,
+    actor: Option<&str>,
+    action: Option<&str>,
+    since: Option<NaiveDateTime>,
+    until: Option<NaiveDateTime>,
+    limit: i64,
+) -> Result<Vec<AuditEvent>, AppError> {
+    sqlx::query_as!(
+        AuditEvent,
+        r#"SELECT
+            id as "id!",
+            created_at as "created_at!: NaiveDateTime",
+            actor,
+            action as "action!",
+            target,
+            details
+        FROM audit_events
+        WHERE (? IS NULL OR actor = ?)
+            AND (? IS NULL OR action = ?)
+            AND (? IS NULL OR created_at >= ?)
+            AND (? IS NULL OR created_at < ?)
+        ORDER BY id DESC
+        LIMIT ?"#,
+        actor,
+        actor,
+        action,
+        action,
+        since,
+        since,
+        until,
+        until,
+        limit,
+    )
+    .fetch_all(db)
+    .await
+    .map_err(AppError::from)
+}
+
+pub async fn create(db: &SqlitePool, event: &AuditEvent) -> Result<(), AppError> {
+    sqlx::query!(
+        "INSERT INTO audit_events (created_at, actor, action, target, details) VALUES (?, ?, ?, ?, ?)",
+        event.created_at,
+        event.actor,
+        event.action,
+        event.target,
+        event.details,
+    )
+    .execute(db)
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,38 @@
+use crate::{error::AppError, models::chapter_cache::CachedChapter};
+use sqlx::SqlitePool;
+
+pub async fn find_all(db: &SqlitePool) -> Result<Vec<CachedChapter>, AppError> {
+    sqlx::query_as!(
+        CachedChapter,
+        r#"SELECT
+            path as "path!",
+            size as "size!",
+            mtime_ns as "mtime_ns!",
+            duration_us as "duration_us!"
+        FROM chapter_cache"#
+    )
+    .fetch_all(db)
+    .await
+    .map_err(AppError::from)
+}
+
+pub async fn upsert(db: &SqlitePool, chapter: &CachedChapter) -> Result<(), AppError> {
+    sqlx::query!(
+        "INSERT INTO chapter_cache (path, size, mtime_ns, duration_us) VALUES (?, ?, ?, ?)
+        ON CONFLICT (path) DO UPDATE SET size = excluded.size, mtime_ns = excluded.mtime_ns, duration_us = excluded.duration_us",
+        chapter.path,
+        chapter.size,
+        chapter.mtime_ns,
+        chapter.duration_us,
+    )
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+pub async fn delete(db: &SqlitePool, path: &str) -> Result<(), AppError> {
+    sqlx::query!("DELETE FROM chapter_cache WHERE path = ?", path)
+        .execute(db)
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,148 @@
+use crate::{error::AppError, models::group::Group};
+use chrono::NaiveDateTime;
+use sqlx::SqlitePool;
+
+pub async fn find_all(db: &SqlitePool) -> Result<Vec<Group>, AppError> {
+    sqlx::query_as!(
+        Group,
+        r#"SELECT
+            name as "name!",
+            created_at as "created_at!: NaiveDateTime"
+        FROM groups ORDER BY name"#
+    )
+    .fetch_all(db)
+    .await
+    .map_err(AppError::from)
+}
+
+pub async fn find_by_name(db: &SqlitePool, name: &str) -> Result<Option<Group>, AppError> {
+    sqlx::query_as!(
+        Group,
+        r#"SELECT
+            name as "name!",
+            created_at as "created_at!: NaiveDateTime"
+        FROM groups WHERE name = ?"#,
+        name
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(AppError::from)
+}
+
+pub async fn find_members(db: &SqlitePool, name: &str) -> Result<Vec<String>, AppError> {
+    sqlx::query_scalar!(
+        r#"SELECT username as "username!" FROM group_members WHERE group_name = ? ORDER BY username"#,
+        name
+    )
+    .fetch_all(db)
+    .await
+    .map_err(AppError::from)
+}
+
+pub async fn find_audiobooks(db: &SqlitePool, name: &str) -> Result<Vec<String>, AppError> {
+    sqlx::query_scalar!(
+        r#"SELECT audiobook_hash as "audiobook_hash!" FROM audiobook_groups WHERE group_name = ? ORDER BY audiobook_hash"#,
+        name
+    )
+    .fetch_all(db)
+    .await
+    .map_err(AppError::from)
+}
+
+// Same rules as audiobook_repo::find_visible: missing books are hidden too.
+pub async fn can_access(db: &SqlitePool, hash: &str, username: &str) -> Result<bool, AppError> {
+    sqlx::query_scalar!(
+        r#"SELECT (
+            NOT EXISTS (SELECT 1 FROM audiobooks WHERE hash = ? AND missing_since IS NOT NULL)
+            AND (
+                NOT EXISTS (SELECT 1 FROM audiobook_groups WHERE audiobook_hash = ?)
+                OR EXISTS (
+                    SELECT 1 FROM audiobook_groups ag
+                    JOIN group_members gm ON gm.group_name = ag.group_name
+                    WHERE ag.audiobook_hash = ? AND gm.username = ?
+                )
+            )
+        ) as "allowed!: bool""#,
+        hash,
+        hash,
+        hash,
+        username,
+    )
+    .fetch_one(db)
+    .await
+    .map_err(AppError::from)
+}
+
+pub async fn create(db: &SqlitePool, group: &Group) -> Result<(), AppError> {
+    sqlx::query!(
+        "INSERT INTO groups (name, created_at) VALUES (?, ?)",
+        group.name,
+        group.created_at,
+    )
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+// Only deletes a group that has no audio books left.
+pub async fn delete(db: &SqlitePool, name: &str) -> Result<u64, AppError> {
+    let mut tx = db.begin().await?;
+    let result = sqlx::query!(
+        "DELETE FROM groups WHERE name = ? AND NOT EXISTS (SELECT 1 FROM audiobook_groups WHERE group_name = ?)",
+        name,
+        name,
+    )
+    .execute(&mut *tx)
+    .await?;
+    if result.rows_affected() > 0 {
+        sqlx::query!("DELETE FROM group_members WHERE group_name = ?", name)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+    Ok(result.rows_affected())
+}
+
+pub async fn add_member(db: &SqlitePool, name: &str, username: &str) -> Result<(), AppError> {
+    sqlx::query!(
+        "INSERT OR IGNORE INTO group_members (group_name, username) VALUES (?, ?)",
+        name,
+        username,
+    )
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+pub async fn remove_member(db: &SqlitePool, name: &str, username: &str) -> Result<u64, AppError> {
+    let result = sqlx::query!(
+        "DELETE FROM group_members WHERE group_name = ? AND username = ?",
+        name,
+        username,
+    )
+    .execute(db)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+pub async fn add_audiobook(db: &SqlitePool, name: &str, hash: &str) -> Result<(), AppError> {
+    sqlx::query!(
+        "INSERT OR IGNORE INTO audiobook_groups (audiobook_hash, group_name) VALUES (?, ?)",
+        hash,
+        name,
+    )
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+pub async fn remove_audiobook(db: &SqlitePool, name: &str, hash: &str) -> Result<u64, AppError> {
+    let result = sqlx::query!(
+        "DELETE FROM audiobook_groups WHERE group_name = ? AND audiobook_hash = ?",
+        name,
+        hash,
+    )
+    .execute(db)
+    .await?;
+    Ok(result.rows_affected())
+}
@@ -0,0 +1,43 @@
+use crate::{error::AppError, models::invite::Invite};
+use chrono::NaiveDateTime;
+use sqlx::SqlitePool;
+
+pub async fn find_all(db: &SqlitePool) -> Result<Vec<Invite>, AppError> {
+    sqlx::query_as!(
+        Invite,
+        r#"SELECT
+            id as "id!",
+            code_hash as "code_hash!",
+            created_by as "created_by!",
+            max_uses as "max_uses!",
+            uses as "uses!",
+            created_at as "created_at!: NaiveDateTime",
+            expires_at as "expires_at: NaiveDateTime"
+        FROM invites ORDER BY created_at"#
+    )
+    .fetch_all(db)
+    .await
+    .map_err(AppError::from)
+}
+
+pub async fn create(db: &SqlitePool, invite: &Invite) -> Result<i64, AppError> {
+    let result = sqlx::query!(
+        "INSERT INTO invites (code_hash, created_by, max_uses, uses, created_at, expires_at) VALUES (?, ?, ?, ?, ?, ?)",
+        invite.code_hash,
+        invite.created_by,
+        invite.max_uses,
+        invite.uses,
+        invite.created_at,
+        invite.expires_at,
+    )
+    .execute(db)
+    .await?;
+    Ok(result.last_insert_rowid())
+}
+
+pub async fn delete(db: &SqlitePool, id: i64) -> Result<u64, AppError> {
+    let result = sqlx::query!("DELETE FROM invites WHERE id = ?", id)
+        .execute(db)
+        .await?;
+    Ok(result.rows_affected())
+}
@@ -1,3 +1,10 @@
+pub mod api_key;
 pub mod audiobook;
+pub mod audit;
+pub mod chapter_cache;
+pub mod group;
+pub mod invite;
+pub mod oidc;
 pub mod position;
+pub mod session;
 pub mod user;
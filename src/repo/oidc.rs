@@ -0,0 +1,37 @@
+use crate::{error::AppError, models::oidc::OidcIdentity};
+use chrono::NaiveDateTime;
+use sqlx::SqlitePool;
+
+pub async fn find(
+    db: &SqlitePool,
+    issuer: &str,
+    subject: &str,
+) -> Result<Option<OidcIdentity>, AppError> {
+    sqlx::query_as!(
+        OidcIdentity,
+        r#"SELECT
+            issuer as "issuer!",
+            subject as "subject!",
+            username as "username!",
+            created_at as "created_at!: NaiveDateTime"
+        FROM oidc_identities WHERE issuer = ? AND subject = ?"#,
+        issuer,
+        subject
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(AppError::from)
+}
+
+pub async fn create(db: &SqlitePool, identity: &OidcIdentity) -> Result<(), AppError> {
+    sqlx::query!(
+        "INSERT INTO oidc_identities (issuer, subject, username, created_at) VALUES (?, ?, ?, ?)",
+        identity.issuer,
+        identity.subject,
+        identity.username,
+        identity.created_at,
+    )
+    .execute(db)
+    .await?;
+    Ok(())
+}
@@ -66,15 +66,15 @@ pub async fn update(
     Ok(())
 }
 
-pub async fn delete_old(db: &SqlitePool, cutoff: NaiveDateTime) -> Result<(), AppError> {
-    sqlx::query!("DELETE FROM positions WHERE timestamp < ?", cutoff)
+pub async fn delete_old(db: &SqlitePool, cutoff: NaiveDateTime) -> Result<u64, AppError> {
+    let result = sqlx::query!("DELETE FROM positions WHERE timestamp < ?", cutoff)
         .execute(db)
         .await?;
-    Ok(())
+    Ok(result.rows_affected())
 }
 
-pub async fn delete_beyond_final(db: &SqlitePool) -> Result<(), AppError> {
-    sqlx::query!(
+pub async fn delete_beyond_final(db: &SqlitePool) -> Result<u64, AppError> {
+    let result = sqlx::query!(
         "DELETE FROM positions
          WHERE EXISTS (
              SELECT 1 FROM audiobooks
@@ -86,5 +86,5 @@ pub async fn delete_beyond_final(db: &SqlitePool) -> Result<(), AppError> {
     )
     .execute(db)
     .await?;
-    Ok(())
+    Ok(result.rows_affected())
 }
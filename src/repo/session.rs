@@ -0,0 +1,207 @@
+use crate::{error::AppError, models::session::Session};
+use chrono::NaiveDateTime;
+use sqlx::SqlitePool;
+
+pub async fn find_valid(
+    db: &SqlitePool,
+    token_hash: &str,
+    now: NaiveDateTime,
+) -> Result<Option<Session>, AppError> {
+    sqlx::query_as!(
+        Session,
+        r#"SELECT
+            id as "id!",
+            token_hash as "token_hash!",
+            username as "username!",
+            created_at as "created_at!: NaiveDateTime",
+            last_used_at as "last_used_at!: NaiveDateTime",
+            expires_at as "expires_at!: NaiveDateTime",
+            refresh_token_hash,
+            refresh_expires_at as "refresh_expires_at: NaiveDateTime",
+            client_name,
+            user_agent,
+            ip
+        FROM sessions WHERE token_hash = ? AND expires_at > ?"#,
+        token_hash,
+        now,
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(AppError::from)
+}
+
+pub async fn find_by_refresh(
+    db: &SqlitePool,
+    refresh_token_hash: &str,
+    now: NaiveDateTime,
+) -> Result<Option<Session>, AppError> {
+    sqlx::query_as!(
+        Session,
+        r#"SELECT
+            id as "id!",
+            token_hash as "token_hash!",
+            username as "username!",
+            created_at as "created_at!: NaiveDateTime",
+            last_used_at as "last_used_at!: NaiveDateTime",
+            expires_at as "expires_at!: NaiveDateTime",
+            refresh_token_hash,
+            refresh_expires_at as "refresh_expires_at: NaiveDateTime",
+            client_name,
+            user_agent,
+            ip
+        FROM sessions WHERE refresh_token_hash = ? AND refresh_expires_at > ?"#,
+        refresh_token_hash,
+        now,
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(AppError::from)
+}
+
+pub async fn find_by_username(
+    db: &SqlitePool,
+    username: &str,
+    now: NaiveDateTime,
+) -> Result<Vec<Session>, AppError> {
+    sqlx::query_as!(
+        Session,
+        r#"SELECT
+            id as "id!",
+            token_hash as "token_hash!",
+            username as "username!",
+            created_at as "created_at!: NaiveDateTime",
+            last_used_at as "last_used_at!: NaiveDateTime",
+            expires_at as "expires_at!: NaiveDateTime",
+            refresh_token_hash,
+            refresh_expires_at as "refresh_expires_at: NaiveDateTime",
+            client_name,
+            user_agent,
+            ip
+        FROM sessions
+        WHERE username = ? AND (expires_at > ? OR refresh_expires_at > ?)
+        ORDER BY last_used_at DESC"#,
+        username,
+        now,
+        now,
+    )
+    .fetch_all(db)
+    .await
+    .map_err(AppError::from)
+}
+
+pub async fn create(db: &SqlitePool, session: &Session) -> Result<i64, AppError> {
+    let result = sqlx::query!(
+        "INSERT INTO sessions (token_hash, refresh_token_hash, username, created_at, last_used_at, expires_at, refresh_expires_at, client_name, user_agent, ip) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        session.token_hash,
+        session.refresh_token_hash,
+        session.username,
+        session.created_at,
+        session.last_used_at,
+        session.expires_at,
+        session.refresh_expires_at,
+        session.client_name,
+        session.user_agent,
+        session.ip,
+    )
+    .execute(db)
+    .await?;
+    Ok(result.last_insert_rowid())
+}
+
+pub async fn rotate(
+    db: &SqlitePool,
+    id: i64,
+    token_hash: &str,
+    refresh_token_hash: &str,
+    now: NaiveDateTime,
+    expires_at: NaiveDateTime,
+    refresh_expires_at: NaiveDateTime,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        "UPDATE sessions SET token_hash = ?, refresh_token_hash = ?, last_used_at = ?, expires_at = ?, refresh_expires_at = ? WHERE id = ?",
+        token_hash,
+        refresh_token_hash,
+        now,
+        expires_at,
+        refresh_expires_at,
+        id,
+    )
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+pub async fn touch(db: &SqlitePool, id: i64, now: NaiveDateTime) -> Result<(), AppError> {
+    sqlx::query!("UPDATE sessions SET last_used_at = ? WHERE id = ?", now, id)
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+pub async fn extend(
+    db: &SqlitePool,
+    id: i64,
+    now: NaiveDateTime,
+    expires_at: NaiveDateTime,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        "UPDATE sessions SET last_used_at = ?, expires_at = MAX(expires_at, ?) WHERE id = ?",
+        now,
+        expires_at,
+        id,
+    )
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+pub async fn delete(db: &SqlitePool, id: i64) -> Result<(), AppError> {
+    sqlx::query!("DELETE FROM sessions WHERE id = ?", id)
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+pub async fn delete_for_user(db: &SqlitePool, id: i64, username: &str) -> Result<u64, AppError> {
+    let result = sqlx::query!(
+        "DELETE FROM sessions WHERE id = ? AND username = ?",
+        id,
+        username,
+    )
+    .execute(db)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+pub async fn delete_by_username(db: &SqlitePool, username: &str) -> Result<u64, AppError> {
+    let result = sqlx::query!("DELETE FROM sessions WHERE username = ?", username)
+        .execute(db)
+        .await?;
+    Ok(result.rows_affected())
+}
+
+pub async fn delete_by_username_except(
+    db: &SqlitePool,
+    username: &str,
+    id: i64,
+) -> Result<u64, AppError> {
+    let result = sqlx::query!(
+        "DELETE FROM sessions WHERE username = ? AND id != ?",
+        username,
+        id,
+    )
+    .execute(db)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+pub async fn delete_expired(db: &SqlitePool, now: NaiveDateTime) -> Result<u64, AppError> {
+    let result = sqlx::query!(
+        "DELETE FROM sessions WHERE expires_at <= ? AND (refresh_expires_at IS NULL OR refresh_expires_at <= ?)",
+        now,
+        now,
+    )
+    .execute(db)
+    .await?;
+    Ok(result.rows_affected())
+}
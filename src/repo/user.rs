@@ -1,12 +1,31 @@
-use crate::{error::AppError, models::user::User};
+use crate::{
+    error::AppError,
+    models::user::{fold_username, User},
+};
+use chrono::NaiveDateTime;
 use sqlx::SqlitePool;
 
+pub async fn find_all(db: &SqlitePool) -> Result<Vec<User>, AppError> {
+    sqlx::query_as!(
+        User,
+        r#"SELECT
+            username as "username!",
+            password_hash as "password_hash!",
+            is_admin as "is_admin!: bool"
+        FROM users ORDER BY username"#
+    )
+    .fetch_all(db)
+    .await
+    .map_err(AppError::from)
+}
+
 pub async fn find_by_username(db: &SqlitePool, username: &str) -> Result<Option<User>, AppError> {
     sqlx::query_as!(
         User,
         r#"SELECT
             username as "username!",
-            password_hash as "password_hash!"
+            password_hash as "password_hash!",
+            is_admin as "is_admin!: bool"
         FROM users WHERE username = ?"#,
         username
     )
@@ -15,13 +34,248 @@ pub async fn find_by_username(db: &SqlitePool, username: &str) -> Result<Option<
     .map_err(AppError::from)
 }
 
-pub async fn create(db: &SqlitePool, username: &str, password_hash: &str) -> Result<(), AppError> {
+pub async fn find_by_folded_username(
+    db: &SqlitePool,
+    username: &str,
+) -> Result<Option<User>, AppError> {
+    let folded = fold_username(username);
+    sqlx::query_as!(
+        User,
+        r#"SELECT
+            username as "username!",
+            password_hash as "password_hash!",
+            is_admin as "is_admin!: bool"
+        FROM users WHERE username_folded = ?"#,
+        folded
+    )
+    .fetch_optional(db)
+    .await
+    .map_err(AppError::from)
+}
+
+// Updates folded usernames that are out of date, such as the ones the
+// migration backfilled with SQLite's ASCII-only lower(). Returns how many were
+// updated and the usernames whose folded form is already taken.
+pub async fn refold_all(db: &SqlitePool) -> Result<(u64, Vec<String>), AppError> {
+    let rows = sqlx::query!(
+        r#"SELECT username as "username!", username_folded as "username_folded!" FROM users"#
+    )
+    .fetch_all(db)
+    .await?;
+    let mut tx = db.begin().await?;
+    let mut updated = 0;
+    let mut taken = Vec::new();
+    for row in rows {
+        let folded = fold_username(&row.username);
+        if folded == row.username_folded {
+            continue;
+        }
+        let result = sqlx::query!(
+            "UPDATE OR IGNORE users SET username_folded = ? WHERE username = ?",
+            folded,
+            row.username,
+        )
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() > 0 {
+            updated += 1;
+        } else {
+            taken.push(row.username);
+        }
+    }
+    tx.commit().await?;
+    Ok((updated, taken))
+}
+
+// Groups of usernames that only differ in case or normalization.
+pub async fn find_folded_duplicates(db: &SqlitePool) -> Result<Vec<String>, AppError> {
+    sqlx::query_scalar!(
+        r#"SELECT group_concat(username, ', ') as "usernames!: String"
+        FROM users GROUP BY username_folded HAVING COUNT(*) > 1"#
+    )
+    .fetch_all(db)
+    .await
+    .map_err(AppError::from)
+}
+
+pub async fn create_folded_index(db: &SqlitePool) -> Result<(), AppError> {
+    sqlx::query(
+        "CREATE UNIQUE INDEX IF NOT EXISTS users_username_folded_unique ON users (username_folded)",
+    )
+    .execute(db)
+    .await?;
+    sqlx::query("DROP INDEX IF EXISTS users_username_folded")
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+pub async fn count_admins(db: &SqlitePool) -> Result<i64, AppError> {
+    sqlx::query_scalar!(r#"SELECT COUNT(*) as "count!: i64" FROM users WHERE is_admin = 1"#)
+        .fetch_one(db)
+        .await
+        .map_err(AppError::from)
+}
+
+pub async fn create(
+    db: &SqlitePool,
+    username: &str,
+    password_hash: &str,
+    is_admin: bool,
+) -> Result<(), AppError> {
+    let folded = fold_username(username);
+    sqlx::query!(
+        "INSERT INTO users (username, username_folded, password_hash, is_admin) VALUES (?, ?, ?, ?)",
+        username,
+        folded,
+        password_hash,
+        is_admin,
+    )
+    .execute(db)
+    .await
+    .map_err(conflict_on_unique)?;
+    Ok(())
+}
+
+// The invite is only spent if the user is created. Returns false without
+// creating anything if the invite is unknown, used up or expired.
+pub async fn create_with_invite(
+    db: &SqlitePool,
+    username: &str,
+    password_hash: &str,
+    invite_code_hash: &str,
+    now: NaiveDateTime,
+) -> Result<bool, AppError> {
+    let folded = fold_username(username);
+    let mut tx = db.begin().await?;
+    let consumed = sqlx::query!(
+        "UPDATE invites SET uses = uses + 1 WHERE code_hash = ? AND uses < max_uses AND (expires_at IS NULL OR expires_at > ?)",
+        invite_code_hash,
+        now,
+    )
+    .execute(&mut *tx)
+    .await?;
+    if consumed.rows_affected() != 1 {
+        return Ok(false);
+    }
     sqlx::query!(
-        "INSERT INTO users (username, password_hash) VALUES (?, ?)",
+        "INSERT INTO users (username, username_folded, password_hash, is_admin) VALUES (?, ?, ?, 0)",
         username,
+        folded,
         password_hash,
     )
+    .execute(&mut *tx)
+    .await
+    .map_err(conflict_on_unique)?;
+    tx.commit().await?;
+    Ok(true)
+}
+
+pub async fn update_password(
+    db: &SqlitePool,
+    username: &str,
+    password_hash: &str,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        "UPDATE users SET password_hash = ? WHERE username = ?",
+        password_hash,
+        username,
+    )
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+pub async fn set_admin(db: &SqlitePool, username: &str, is_admin: bool) -> Result<(), AppError> {
+    sqlx::query!(
+        "UPDATE users SET is_admin = ? WHERE username = ?",
+        is_admin,
+        username,
+    )
     .execute(db)
     .await?;
     Ok(())
 }
+
+pub async fn rename(db: &SqlitePool, username: &str, new_username: &str) -> Result<(), AppError> {
+    let folded = fold_username(new_username);
+    let mut tx = db.begin().await?;
+    sqlx::query!(
+        "UPDATE users SET username = ?, username_folded = ? WHERE username = ?",
+        new_username,
+        folded,
+        username,
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(conflict_on_unique)?;
+    sqlx::query!(
+        "UPDATE positions SET username = ? WHERE username = ?",
+        new_username,
+        username,
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!(
+        "UPDATE sessions SET username = ? WHERE username = ?",
+        new_username,
+        username,
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!(
+        "UPDATE api_keys SET username = ? WHERE username = ?",
+        new_username,
+        username,
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!(
+        "UPDATE oidc_identities SET username = ? WHERE username = ?",
+        new_username,
+        username,
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!(
+        "UPDATE group_members SET username = ? WHERE username = ?",
+        new_username,
+        username,
+    )
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+pub async fn delete(db: &SqlitePool, username: &str) -> Result<(), AppError> {
+    let mut tx = db.begin().await?;
+    sqlx::query!("DELETE FROM positions WHERE username = ?", username)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query!("DELETE FROM sessions WHERE username = ?", username)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query!("DELETE FROM api_keys WHERE username = ?", username)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query!("DELETE FROM oidc_identities WHERE username = ?", username)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query!("DELETE FROM group_members WHERE username = ?", username)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query!("DELETE FROM users WHERE username = ?", username)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+// Another request took the name between the caller's check and the write.
+fn conflict_on_unique(e: sqlx::Error) -> AppError {
+    match &e {
+        sqlx::Error::Database(db_error) if db_error.is_unique_violation() => AppError::Conflict,
+        _ => AppError::from(e),
+    }
+}
@@ -0,0 +1,125 @@
+use crate::{
+    error::AppError,
+    inputs::api_key::ApiKeyCreate,
+    models::{
+        api_key::{ApiKey, SCOPE_FULL, SCOPE_LIBRARY_ADMIN, SCOPE_READ_ONLY},
+        audit::{ACTION_API_KEY_CREATE, ACTION_API_KEY_REVOKE},
+    },
+    outputs::api_key::{ApiKeyCreated, ApiKeyInfo},
+    repo::{api_key as api_key_repo, user as user_repo},
+    services::audit,
+    services::auth::{add_ttl, generate_token, hash_token},
+    state::AppState,
+};
+use chrono::Utc;
+use std::time::Duration;
+
+pub const API_KEY_PREFIX: &str = "iliad_";
+const MAX_EXPIRES_IN_DAYS: u64 = 3650;
+
+pub async fn list_api_keys(username: String, state: &AppState) -> Result<Vec<ApiKeyInfo>, AppError> {
+    let keys = api_key_repo::find_by_username(&state.db, &username).await?;
+
+    Ok(keys
+        .into_iter()
+        .map(|key| ApiKeyInfo {
+            id: key.id,
+            name: key.name,
+            scope: key.scope,
+            created_at: key.created_at.and_utc().timestamp(),
+            last_used_at: key.last_used_at.map(|t| t.and_utc().timestamp()),
+            expires_at: key.expires_at.map(|t| t.and_utc().timestamp()),
+        })
+        .collect())
+}
+
+pub async fn create_api_key(
+    username: String,
+    input: ApiKeyCreate,
+    state: &AppState,
+) -> Result<ApiKeyCreated, AppError> {
+    let user = user_repo::find_by_username(&state.db, &username)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    let name = input.name.trim().to_string();
+    if name.is_empty() {
+        return Err(AppError::BadRequest("api key name must not be empty".into()));
+    }
+
+    let scope = input.scope.unwrap_or_else(|| SCOPE_FULL.to_string());
+    match scope.as_str() {
+        SCOPE_FULL | SCOPE_READ_ONLY => {}
+        SCOPE_LIBRARY_ADMIN if user.is_admin => {}
+        SCOPE_LIBRARY_ADMIN => {
+            return Err(AppError::BadRequest(
+                "only admins can create library-admin api keys".into(),
+            ))
+        }
+        other => return Err(AppError::BadRequest(format!("unknown api key scope '{other}'"))),
+    }
+
+    if api_key_repo::find_by_name(&state.db, &username, &name)
+        .await?
+        .is_some()
+    {
+        return Err(AppError::Conflict);
+    }
+
+    if input.expires_in_days.is_some_and(|days| days > MAX_EXPIRES_IN_DAYS) {
+        return Err(AppError::BadRequest(format!(
+            "expires_in_days must be at most {MAX_EXPIRES_IN_DAYS}"
+        )));
+    }
+
+    let now = Utc::now().naive_utc();
+    let expires_at = input
+        .expires_in_days
+        .map(|days| add_ttl(now, Duration::from_secs(days * 24 * 3600)))
+        .transpose()?;
+
+    let key = format!("{API_KEY_PREFIX}{}", generate_token());
+    let mut api_key = ApiKey {
+        id: 0,
+        username,
+        name,
+        key_hash: hash_token(&key),
+        scope,
+        created_at: now,
+        last_used_at: None,
+        expires_at,
+    };
+    api_key.id = api_key_repo::create(&state.db, &api_key).await?;
+    tracing::info!("created api key {} for {}", api_key.name, api_key.username);
+    audit::record(
+        state,
+        Some(&api_key.username),
+        ACTION_API_KEY_CREATE,
+        Some(&api_key.name),
+        Some(format!("scope {}", api_key.scope)),
+    )
+    .await;
+
+    Ok(ApiKeyCreated {
+        id: api_key.id,
+        name: api_key.name,
+        scope: api_key.scope,
+        key,
+        expires_at: api_key.expires_at.map(|t| t.and_utc().timestamp()),
+    })
+}
+
+pub async fn revoke_api_key(username: String, id: i64, state: &AppState) -> Result<(), AppError> {
+    if api_key_repo::delete(&state.db, id, &username).await? == 0 {
+        return Err(AppError::NotFound);
+    }
+    audit::record(
+        state,
+        Some(&username),
+        ACTION_API_KEY_REVOKE,
+        Some(&id.to_string()),
+        None,
+    )
+    .await;
+    Ok(())
+}
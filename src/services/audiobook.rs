@@ -1,17 +1,49 @@
 use crate::{
     error::AppError,
+    inputs::audiobook::AudiobookQuery,
+    models::audiobook::Audiobook,
     outputs::audiobook::{AudiobookLong, AudiobookShort},
-    repo::audiobook as audiobook_repo,
+    repo::{audiobook as audiobook_repo, user as user_repo},
+    services::group::ensure_access,
     state::AppState,
 };
 use base64::{engine::general_purpose::STANDARD, Engine};
 use std::{fs, path::PathBuf};
 
-pub async fn list_audiobooks(state: &AppState) -> Result<Vec<AudiobookShort>, AppError> {
-    let audiobooks = audiobook_repo::find_all(&state.db).await?;
+// Hashes that a book had before its metadata changed stay usable as aliases.
+pub async fn resolve_hash(hash: String, state: &AppState) -> Result<String, AppError> {
+    Ok(audiobook_repo::find_alias(&state.db, &hash).await?.unwrap_or(hash))
+}
+
+pub async fn visible_audiobooks(
+    username: &str,
+    state: &AppState,
+) -> Result<Vec<Audiobook>, AppError> {
+    let is_admin = user_repo::find_by_username(&state.db, username)
+        .await?
+        .is_some_and(|user| user.is_admin);
+    if is_admin {
+        audiobook_repo::find_all(&state.db).await
+    } else {
+        audiobook_repo::find_visible(&state.db, username).await
+    }
+}
 
-    Ok(audiobooks
+pub async fn list_audiobooks(
+    username: String,
+    query: AudiobookQuery,
+    state: &AppState,
+) -> Result<Vec<AudiobookShort>, AppError> {
+    if let Some(library) = &query.library {
+        if !state.libraries.iter().any(|l| &l.name == library) {
+            return Err(AppError::NotFound);
+        }
+    }
+
+    Ok(visible_audiobooks(&username, state)
+        .await?
         .into_iter()
+        .filter(|book| query.library.as_ref().is_none_or(|l| l == &book.library))
         .map(|book| AudiobookShort {
             hash: book.hash,
             title: book.title,
@@ -20,11 +52,19 @@ pub async fn list_audiobooks(state: &AppState) -> Result<Vec<AudiobookShort>, Ap
             genres: serde_json::from_str(&book.genres).unwrap_or_default(),
             duration: book.duration,
             archive_ready: book.archive_ready,
+            library: book.library,
+            missing_since: book.missing_since.map(|t| t.and_utc().timestamp()),
         })
         .collect())
 }
 
-pub async fn get_audiobook_by_hash(hash: String, state: &AppState) -> Result<AudiobookLong, AppError> {
+pub async fn get_audiobook_by_hash(
+    username: String,
+    hash: String,
+    state: &AppState,
+) -> Result<AudiobookLong, AppError> {
+    let hash = resolve_hash(hash, state).await?;
+    ensure_access(&username, &hash, state).await?;
     let book = audiobook_repo::find_by_hash(&state.db, &hash)
         .await?
         .ok_or(AppError::NotFound)?;
@@ -43,13 +83,25 @@ pub async fn get_audiobook_by_hash(hash: String, state: &AppState) -> Result<Aud
             fs::read(&path).ok().map(|bytes| STANDARD.encode(&bytes))
         }),
         archive_ready: book.archive_ready,
+        library: book.library,
+        missing_since: book.missing_since.map(|t| t.and_utc().timestamp()),
+        missing_reason: book.missing_reason,
     })
 }
 
-pub async fn get_audiobook_archive(hash: String, state: &AppState) -> Result<(String, PathBuf), AppError> {
+pub async fn get_audiobook_archive(
+    username: String,
+    hash: String,
+    state: &AppState,
+) -> Result<(String, PathBuf), AppError> {
+    let hash = resolve_hash(hash, state).await?;
+    ensure_access(&username, &hash, state).await?;
     let audiobook = audiobook_repo::find_by_hash(&state.db, &hash)
         .await?
         .ok_or(AppError::NotFound)?;
+    if audiobook.missing_since.is_some() {
+        return Err(AppError::NotFound);
+    }
 
     if !audiobook.archive_ready {
         let mut q = state.archive_queue.lock().unwrap();
@@ -0,0 +1,70 @@
+use crate::{
+    error::AppError, inputs::audit::AuditQuery, models::audit::AuditEvent,
+    outputs::audit::AuditEventInfo, repo::audit as audit_repo, state::AppState,
+};
+use chrono::{DateTime, NaiveDateTime, Utc};
+
+const DEFAULT_LIMIT: i64 = 100;
+const MAX_LIMIT: i64 = 1000;
+
+// A failure to write the audit log is logged but never fails the action
+// being audited.
+pub async fn record(
+    state: &AppState,
+    actor: Option<&str>,
+    action: &str,
+    target: Option<&str>,
+    details: Option<String>,
+) {
+    let event = AuditEvent {
+        id: 0,
+        created_at: Utc::now().naive_utc(),
+        actor: actor.map(String::from),
+        action: action.to_string(),
+        target: target.map(String::from),
+        details,
+    };
+    if let Err(e) = audit_repo::create(&state.db, &event).await {
+        tracing::error!("failed to record audit event {}: {}", action, e);
+    }
+}
+
+pub async fn list_events(
+    query: AuditQuery,
+    state: &AppState,
+) -> Result<Vec<AuditEventInfo>, AppError> {
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT);
+    if !(1..=MAX_LIMIT).contains(&limit) {
+        return Err(AppError::BadRequest(format!(
+            "limit must be between 1 and {MAX_LIMIT}"
+        )));
+    }
+
+    let events = audit_repo::find(
+        &state.db,
+        query.actor.as_deref(),
+        query.action.as_deref(),
+        query.since.map(timestamp).transpose()?,
+        query.until.map(timestamp).transpose()?,
+        limit,
+    )
+    .await?;
+
+    Ok(events
+        .into_iter()
+        .map(|event| AuditEventInfo {
+            id: event.id,
+            created_at: event.created_at.and_utc().timestamp(),
+            actor: event.actor,
+            action: event.action,
+            target: event.target,
+            details: event.details,
+        })
+        .collect())
+}
+
+fn timestamp(secs: i64) -> Result<NaiveDateTime, AppError> {
+    DateTime::from_timestamp(secs, 0)
+        .map(|t| t.naive_utc())
+        .ok_or_else(|| AppError::BadRequest(format!("invalid timestamp {secs}")))
+}
@@ -1,79 +1,463 @@
 use crate::{
     error::AppError,
-    inputs::auth::{AdminLogin, RegularLogin, RegularRegister},
-    outputs::auth::AuthToken,
-    repo::user as user_repo,
-    state::AppState,
+    config::Config,
+    inputs::auth::{
+        AccountDeletion, ClientInfo, PasswordChange, RegularLogin, RegularRegister, TokenRefresh,
+    },
+    models::{
+        audit::{
+            ACTION_ACCOUNT_DELETE, ACTION_LOGIN, ACTION_LOGIN_FAILED, ACTION_PASSWORD_CHANGE,
+            ACTION_REGISTER, ACTION_SESSION_REVOKE, ACTION_USER_PROVISION,
+            ACTION_USER_SESSIONS_REVOKE,
+        },
+        session::Session,
+        user::{fold_username, normalize_username},
+    },
+    outputs::auth::{AuthToken, SessionInfo},
+    repo::{api_key as api_key_repo, session as session_repo, user as user_repo},
+    services::audit,
+    services::validation::{self, check_password, check_username},
+    state::{AppState, LoginAttempts},
 };
 use argon2::{
     password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
     Argon2,
 };
+use chrono::{NaiveDateTime, Utc};
 use rand::distr::SampleString;
 use rand_core::OsRng;
-use std::time::Instant;
+use sha2::{Digest, Sha256};
+use std::fmt::Write as FmtWrite;
+use std::time::{Duration, Instant};
 
-pub async fn login(input: RegularLogin, state: &AppState) -> Result<AuthToken, AppError> {
-    let user = user_repo::find_by_username(&state.db, &input.username)
-        .await?
-        .ok_or(AppError::NotFound)?;
+// Failed logins from one address may reach this multiple of the per-user limit.
+const IP_ATTEMPTS_FACTOR: u32 = 4;
 
-    let parsed = PasswordHash::new(&user.password_hash)
-        .map_err(|_| AppError::Internal("invalid password hash in db".into()))?;
-    Argon2::default()
-        .verify_password(input.password.as_bytes(), &parsed)
-        .map_err(|_| AppError::Unauthorized)?;
+// Argon2 hash of a random password nobody knows, with the default parameters.
+const DUMMY_PASSWORD_HASH: &str =
+    "$argon2id$v=19$m=19456,t=2,p=1$e5zRgnQYsKoTh3qlTryxZw$h9RJ4wpm8muIQACdB8uSYtZ/M0cABA7/Iy2/X2px3x0";
 
-    let token = generate_token();
-    let mut tokens = state
-        .regular_tokens
-        .lock()
-        .map_err(|_| AppError::Internal("lock poisoned".into()))?;
-    tokens.retain(|_, (_, created)| created.elapsed() < state.token_ttl);
-    tokens.insert(token.clone(), (input.username, Instant::now()));
+pub async fn login(
+    input: RegularLogin,
+    client: ClientInfo,
+    state: &AppState,
+) -> Result<AuthToken, AppError> {
+    let username = normalize_username(&input.username);
+    let mut throttle_keys = vec![format!("user:{}", fold_username(&username))];
+    if let Some(ip) = &client.ip {
+        throttle_keys.push(format!("ip:{ip}"));
+    }
+    check_throttle(state, &throttle_keys)?;
+
+    // An exact match wins for accounts that predate case-insensitive names.
+    let user = match user_repo::find_by_username(&state.db, &username).await? {
+        Some(user) => Some(user),
+        None => user_repo::find_by_folded_username(&state.db, &username).await?,
+    };
 
-    Ok(AuthToken { token })
+    // Verify against a dummy hash for unknown users so that both failures
+    // take the same time and return the same error.
+    let verified = match &user {
+        Some(user) => verify_password(&input.password, &user.password_hash),
+        None => verify_password(&input.password, DUMMY_PASSWORD_HASH)
+            .and(Err(AppError::Unauthorized)),
+    };
+    let ip_details = client.ip.as_ref().map(|ip| format!("ip {ip}"));
+    if let Err(e) = verified {
+        record_login_failure(state, &throttle_keys)?;
+        audit::record(state, None, ACTION_LOGIN_FAILED, Some(&username), ip_details).await;
+        return Err(e);
+    }
+
+    let username = user.map_or(username, |user| user.username);
+    record_login_success(state, &throttle_keys[0])?;
+    audit::record(state, Some(&username), ACTION_LOGIN, None, ip_details).await;
+    issue_session(state, &username, client).await
 }
 
-pub async fn admin_login(input: AdminLogin, state: &AppState) -> Result<AuthToken, AppError> {
-    if input.password != state.admin_password {
+pub async fn register(
+    input: RegularRegister,
+    caller: Option<String>,
+    client: ClientInfo,
+    state: &AppState,
+) -> Result<AuthToken, AppError> {
+    let caller_is_admin = match &caller {
+        Some(caller) => user_repo::find_by_username(&state.db, caller)
+            .await?
+            .is_some_and(|user| user.is_admin),
+        None => false,
+    };
+    let needs_invite = !state.public_register && !caller_is_admin;
+    if needs_invite && input.invite.is_none() {
         return Err(AppError::Unauthorized);
     }
 
+    let mut errors = Vec::new();
+    let username = check_username("username", &input.username, state, &mut errors);
+    check_password("password", &input.password, state, &mut errors);
+    validation::finish(errors)?;
+
+    if user_repo::find_by_folded_username(&state.db, &username)
+        .await?
+        .is_some()
+    {
+        return Err(AppError::Conflict);
+    }
+
+    let password_hash = hash_password(&input.password)?;
+    match (needs_invite, &input.invite) {
+        (true, Some(invite)) => {
+            let code_hash = hash_token(invite);
+            let now = Utc::now().naive_utc();
+            if !user_repo::create_with_invite(&state.db, &username, &password_hash, &code_hash, now)
+                .await?
+            {
+                return Err(AppError::Unauthorized);
+            }
+        }
+        _ => user_repo::create(&state.db, &username, &password_hash, false).await?,
+    }
+
+    let (actor, via) = match caller {
+        Some(caller) if caller_is_admin => (caller, "admin"),
+        _ if needs_invite => (username.clone(), "invite"),
+        _ => (username.clone(), "public registration"),
+    };
+    audit::record(
+        state,
+        Some(&actor),
+        ACTION_REGISTER,
+        Some(&username),
+        Some(format!("via {via}")),
+    )
+    .await;
+
+    issue_session(state, &username, client).await
+}
+
+pub async fn refresh(input: TokenRefresh, state: &AppState) -> Result<AuthToken, AppError> {
+    let now = Utc::now().naive_utc();
+    let session = session_repo::find_by_refresh(&state.db, &hash_token(&input.refresh_token), now)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+
     let token = generate_token();
-    let mut tokens = state
-        .admin_tokens
-        .lock()
-        .map_err(|_| AppError::Internal("lock poisoned".into()))?;
-    tokens.retain(|(_, created)| created.elapsed() < state.token_ttl);
-    tokens.push((token.clone(), Instant::now()));
+    let refresh_token = generate_token();
+    let expires_at = add_ttl(now, state.token_ttl)?;
+    let refresh_expires_at = match (state.sliding_expiry, session.refresh_expires_at) {
+        (false, Some(refresh_expires_at)) => refresh_expires_at,
+        _ => add_ttl(now, state.refresh_ttl)?,
+    };
+    session_repo::rotate(
+        &state.db,
+        session.id,
+        &hash_token(&token),
+        &hash_token(&refresh_token),
+        now,
+        expires_at,
+        refresh_expires_at,
+    )
+    .await?;
+
+    Ok(AuthToken {
+        token,
+        refresh_token,
+        expires_in: state.token_ttl.as_secs(),
+    })
+}
+
+pub async fn logout(session: &Session, state: &AppState) -> Result<(), AppError> {
+    session_repo::delete(&state.db, session.id).await
+}
+
+pub async fn logout_all(session: &Session, state: &AppState) -> Result<(), AppError> {
+    session_repo::delete_by_username(&state.db, &session.username).await?;
+    Ok(())
+}
+
+pub async fn list_sessions(
+    username: String,
+    current: Option<&Session>,
+    state: &AppState,
+) -> Result<Vec<SessionInfo>, AppError> {
+    let sessions =
+        session_repo::find_by_username(&state.db, &username, Utc::now().naive_utc()).await?;
+    Ok(sessions
+        .into_iter()
+        .map(|session| SessionInfo {
+            current: current.is_some_and(|c| c.id == session.id),
+            id: session.id,
+            client_name: session.client_name,
+            user_agent: session.user_agent,
+            ip: session.ip,
+            created_at: session.created_at.and_utc().timestamp(),
+            last_used_at: session.last_used_at.and_utc().timestamp(),
+        })
+        .collect())
+}
+
+pub async fn revoke_session(username: String, id: i64, state: &AppState) -> Result<(), AppError> {
+    if session_repo::delete_for_user(&state.db, id, &username).await? == 0 {
+        return Err(AppError::NotFound);
+    }
+    audit::record(
+        state,
+        Some(&username),
+        ACTION_SESSION_REVOKE,
+        Some(&id.to_string()),
+        None,
+    )
+    .await;
+    Ok(())
+}
+
+pub async fn change_password(
+    username: String,
+    session: &Session,
+    input: PasswordChange,
+    state: &AppState,
+) -> Result<(), AppError> {
+    let user = user_repo::find_by_username(&state.db, &username)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    verify_password(&input.current_password, &user.password_hash)?;
+
+    let mut errors = Vec::new();
+    check_password("new_password", &input.new_password, state, &mut errors);
+    validation::finish(errors)?;
+
+    let password_hash = hash_password(&input.new_password)?;
+    user_repo::update_password(&state.db, &username, &password_hash).await?;
+    session_repo::delete_by_username_except(&state.db, &username, session.id).await?;
+    api_key_repo::delete_by_username(&state.db, &username).await?;
+    audit::record(state, Some(&username), ACTION_PASSWORD_CHANGE, None, None).await;
+    Ok(())
+}
 
-    Ok(AuthToken { token })
+pub async fn delete_account(
+    username: String,
+    input: AccountDeletion,
+    state: &AppState,
+) -> Result<(), AppError> {
+    let user = user_repo::find_by_username(&state.db, &username)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    verify_password(&input.password, &user.password_hash)?;
+
+    if user.is_admin && user_repo::count_admins(&state.db).await? <= 1 {
+        return Err(AppError::Conflict);
+    }
+
+    user_repo::delete(&state.db, &username).await?;
+    tracing::info!("user {} deleted their account", username);
+    audit::record(state, Some(&username), ACTION_ACCOUNT_DELETE, None, None).await;
+    Ok(())
 }
 
-pub async fn register(input: RegularRegister, state: &AppState) -> Result<AuthToken, AppError> {
-    if user_repo::find_by_username(&state.db, &input.username)
+pub async fn revoke_user_sessions(
+    actor: String,
+    username: String,
+    state: &AppState,
+) -> Result<(), AppError> {
+    if user_repo::find_by_username(&state.db, &username)
+        .await?
+        .is_none()
+    {
+        return Err(AppError::NotFound);
+    }
+
+    let revoked = session_repo::delete_by_username(&state.db, &username).await?;
+    tracing::info!("revoked {} sessions for {}", revoked, username);
+    audit::record(
+        state,
+        Some(&actor),
+        ACTION_USER_SESSIONS_REVOKE,
+        Some(&username),
+        Some(format!("{revoked} sessions")),
+    )
+    .await;
+    Ok(())
+}
+
+pub async fn bootstrap_admin(config: &Config, state: &AppState) -> Result<(), AppError> {
+    if user_repo::count_admins(&state.db).await? > 0 {
+        return Ok(());
+    }
+
+    let Some(password) = &config.admin_password else {
+        tracing::warn!("no admin account exists, set ILIAD_ADMIN_PASSWORD to create one");
+        return Ok(());
+    };
+
+    // A regular user may already hold the name from before admin accounts
+    // existed; they are left alone rather than promoted or blocking startup.
+    if user_repo::find_by_folded_username(&state.db, &config.admin_username)
         .await?
         .is_some()
     {
-        return Err(AppError::Conflict);
+        tracing::warn!(
+            "not creating admin account: user '{}' already exists, set ILIAD_ADMIN_USERNAME to another name",
+            config.admin_username
+        );
+        return Ok(());
     }
 
-    let password_hash = hash_password(&input.password)?;
-    user_repo::create(&state.db, &input.username, &password_hash).await?;
+    let password_hash = hash_password(password)?;
+    user_repo::create(&state.db, &config.admin_username, &password_hash, true).await?;
+    tracing::info!("created admin account {}", config.admin_username);
+    Ok(())
+}
+
+// Users created from an external identity only log in through it, so their
+// password is a random one nobody knows.
+pub async fn provision_user(state: &AppState, username: &str, source: &str) -> Result<(), AppError> {
+    let password_hash = hash_password(&generate_token())?;
+    user_repo::create(&state.db, username, &password_hash, false).await?;
+    tracing::info!("provisioned user {} from {}", username, source);
+    audit::record(
+        state,
+        None,
+        ACTION_USER_PROVISION,
+        Some(username),
+        Some(format!("from {source}")),
+    )
+    .await;
+    Ok(())
+}
+
+pub async fn prune_sessions(state: &AppState) -> Result<(), AppError> {
+    let pruned = session_repo::delete_expired(&state.db, Utc::now().naive_utc()).await?;
+    if pruned > 0 {
+        tracing::info!("pruned {} expired sessions", pruned);
+    }
+    Ok(())
+}
+
+pub fn hash_token(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .fold(String::with_capacity(64), |mut s, b| {
+            write!(s, "{b:02x}").unwrap();
+            s
+        })
+}
 
+pub async fn issue_session(
+    state: &AppState,
+    username: &str,
+    client: ClientInfo,
+) -> Result<AuthToken, AppError> {
     let token = generate_token();
-    let mut tokens = state
-        .regular_tokens
+    let refresh_token = generate_token();
+    let now = Utc::now().naive_utc();
+    session_repo::create(
+        &state.db,
+        &Session {
+            id: 0,
+            token_hash: hash_token(&token),
+            username: username.to_string(),
+            created_at: now,
+            last_used_at: now,
+            expires_at: add_ttl(now, state.token_ttl)?,
+            refresh_token_hash: Some(hash_token(&refresh_token)),
+            refresh_expires_at: Some(add_ttl(now, state.refresh_ttl)?),
+            client_name: client.client_name,
+            user_agent: client.user_agent,
+            ip: client.ip,
+        },
+    )
+    .await?;
+
+    Ok(AuthToken {
+        token,
+        refresh_token,
+        expires_in: state.token_ttl.as_secs(),
+    })
+}
+
+pub fn add_ttl(now: NaiveDateTime, ttl: Duration) -> Result<NaiveDateTime, AppError> {
+    chrono::Duration::from_std(ttl)
+        .ok()
+        .and_then(|ttl| now.checked_add_signed(ttl))
+        .ok_or_else(|| AppError::Internal(format!("invalid ttl: {ttl:?}")))
+}
+
+fn check_throttle(state: &AppState, keys: &[String]) -> Result<(), AppError> {
+    let attempts = state
+        .login_attempts
         .lock()
         .map_err(|_| AppError::Internal("lock poisoned".into()))?;
-    tokens.retain(|_, (_, created)| created.elapsed() < state.token_ttl);
-    tokens.insert(token.clone(), (input.username, Instant::now()));
 
-    Ok(AuthToken { token })
+    let now = Instant::now();
+    let retry_after = keys
+        .iter()
+        .filter_map(|key| attempts.get(key)?.locked_until)
+        .filter(|until| *until > now)
+        .map(|until| (until - now).as_secs() + 1)
+        .max();
+
+    match retry_after {
+        Some(secs) => Err(AppError::TooManyRequests(secs)),
+        None => Ok(()),
+    }
+}
+
+fn record_login_failure(state: &AppState, keys: &[String]) -> Result<(), AppError> {
+    let mut attempts = state
+        .login_attempts
+        .lock()
+        .map_err(|_| AppError::Internal("lock poisoned".into()))?;
+
+    let now = Instant::now();
+    attempts.retain(|_, a| a.last_failure.elapsed() < state.login_lockout_max);
+    for key in keys {
+        let entry = attempts.entry(key.clone()).or_insert(LoginAttempts {
+            failures: 0,
+            last_failure: now,
+            locked_until: None,
+        });
+        // Many users can share one address, so it takes more failures to
+        // lock all of them out than to lock a single account.
+        let max_attempts = if key.starts_with("ip:") {
+            state.login_max_attempts.saturating_mul(IP_ATTEMPTS_FACTOR)
+        } else {
+            state.login_max_attempts
+        };
+        entry.failures = entry.failures.saturating_add(1);
+        entry.last_failure = now;
+        if entry.failures >= max_attempts {
+            let exponent = (entry.failures - max_attempts).min(16);
+            let lockout = state
+                .login_lockout
+                .checked_mul(2u32.saturating_pow(exponent))
+                .map_or(state.login_lockout_max, |d| d.min(state.login_lockout_max));
+            entry.locked_until = now.checked_add(lockout).or(entry.locked_until);
+            tracing::warn!("login locked for {} after {} failures", key, entry.failures);
+        }
+    }
+    Ok(())
+}
+
+fn record_login_success(state: &AppState, key: &str) -> Result<(), AppError> {
+    state
+        .login_attempts
+        .lock()
+        .map_err(|_| AppError::Internal("lock poisoned".into()))?
+        .remove(key);
+    Ok(())
+}
+
+fn verify_password(password: &str, password_hash: &str) -> Result<(), AppError> {
+    let parsed = PasswordHash::new(password_hash)
+        .map_err(|_| AppError::Internal("invalid password hash in db".into()))?;
+    Argon2::default()
+        .verify_password(password.as_bytes(), &parsed)
+        .map_err(|_| AppError::Unauthorized)
 }
 
-fn hash_password(password: &str) -> Result<String, AppError> {
+pub fn hash_password(password: &str) -> Result<String, AppError> {
     let salt = SaltString::generate(&mut OsRng);
     Argon2::default()
         .hash_password(password.as_bytes(), &salt)
@@ -81,6 +465,6 @@ fn hash_password(password: &str) -> Result<String, AppError> {
         .map_err(|_| AppError::Internal("password hashing failed".into()))
 }
 
-fn generate_token() -> String {
+pub fn generate_token() -> String {
     rand::distr::Alphanumeric.sample_string(&mut rand::rng(), 32)
 }
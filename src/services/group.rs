@@ -0,0 +1,187 @@
+use crate::{
+    error::AppError,
+    inputs::group::GroupCreate,
+    models::{
+        audit::{
+            ACTION_GROUP_AUDIOBOOK_ADD, ACTION_GROUP_AUDIOBOOK_REMOVE, ACTION_GROUP_CREATE,
+            ACTION_GROUP_DELETE, ACTION_GROUP_MEMBER_ADD, ACTION_GROUP_MEMBER_REMOVE,
+        },
+        group::Group,
+    },
+    outputs::group::GroupInfo,
+    repo::{audiobook as audiobook_repo, group as group_repo, user as user_repo},
+    services::{audiobook::resolve_hash, audit},
+    state::AppState,
+};
+use chrono::Utc;
+
+pub async fn list_groups(state: &AppState) -> Result<Vec<GroupInfo>, AppError> {
+    let mut groups = Vec::new();
+    for group in group_repo::find_all(&state.db).await? {
+        groups.push(group_info(group.name, state).await?);
+    }
+    Ok(groups)
+}
+
+pub async fn create_group(
+    actor: String,
+    input: GroupCreate,
+    state: &AppState,
+) -> Result<GroupInfo, AppError> {
+    let name = input.name.trim().to_string();
+    if name.is_empty() {
+        return Err(AppError::BadRequest("group name must not be empty".into()));
+    }
+
+    if group_repo::find_by_name(&state.db, &name).await?.is_some() {
+        return Err(AppError::Conflict);
+    }
+
+    group_repo::create(
+        &state.db,
+        &Group {
+            name: name.clone(),
+            created_at: Utc::now().naive_utc(),
+        },
+    )
+    .await?;
+    tracing::info!("created group {}", name);
+    audit::record(state, Some(&actor), ACTION_GROUP_CREATE, Some(&name), None).await;
+
+    Ok(GroupInfo {
+        name,
+        members: Vec::new(),
+        audiobooks: Vec::new(),
+    })
+}
+
+// Deleting a group with books would make them visible to everyone, so its books
+// have to be removed from it first.
+pub async fn delete_group(actor: String, name: String, state: &AppState) -> Result<(), AppError> {
+    ensure_group(&name, state).await?;
+    if group_repo::delete(&state.db, &name).await? == 0 {
+        return Err(AppError::Conflict);
+    }
+    tracing::info!("deleted group {}", name);
+    audit::record(state, Some(&actor), ACTION_GROUP_DELETE, Some(&name), None).await;
+    Ok(())
+}
+
+pub async fn add_member(
+    actor: String,
+    name: String,
+    username: String,
+    state: &AppState,
+) -> Result<GroupInfo, AppError> {
+    ensure_group(&name, state).await?;
+    user_repo::find_by_username(&state.db, &username)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    group_repo::add_member(&state.db, &name, &username).await?;
+    audit::record(
+        state,
+        Some(&actor),
+        ACTION_GROUP_MEMBER_ADD,
+        Some(&name),
+        Some(format!("user {username}")),
+    )
+    .await;
+    group_info(name, state).await
+}
+
+pub async fn remove_member(
+    actor: String,
+    name: String,
+    username: String,
+    state: &AppState,
+) -> Result<GroupInfo, AppError> {
+    ensure_group(&name, state).await?;
+    if group_repo::remove_member(&state.db, &name, &username).await? == 0 {
+        return Err(AppError::NotFound);
+    }
+    audit::record(
+        state,
+        Some(&actor),
+        ACTION_GROUP_MEMBER_REMOVE,
+        Some(&name),
+        Some(format!("user {username}")),
+    )
+    .await;
+    group_info(name, state).await
+}
+
+pub async fn add_audiobook(
+    actor: String,
+    name: String,
+    hash: String,
+    state: &AppState,
+) -> Result<GroupInfo, AppError> {
+    ensure_group(&name, state).await?;
+    let hash = resolve_hash(hash, state).await?;
+    audiobook_repo::find_by_hash(&state.db, &hash)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    group_repo::add_audiobook(&state.db, &name, &hash).await?;
+    audit::record(
+        state,
+        Some(&actor),
+        ACTION_GROUP_AUDIOBOOK_ADD,
+        Some(&name),
+        Some(format!("audiobook {hash}")),
+    )
+    .await;
+    group_info(name, state).await
+}
+
+pub async fn remove_audiobook(
+    actor: String,
+    name: String,
+    hash: String,
+    state: &AppState,
+) -> Result<GroupInfo, AppError> {
+    ensure_group(&name, state).await?;
+    let hash = resolve_hash(hash, state).await?;
+    if group_repo::remove_audiobook(&state.db, &name, &hash).await? == 0 {
+        return Err(AppError::NotFound);
+    }
+    audit::record(
+        state,
+        Some(&actor),
+        ACTION_GROUP_AUDIOBOOK_REMOVE,
+        Some(&name),
+        Some(format!("audiobook {hash}")),
+    )
+    .await;
+    group_info(name, state).await
+}
+
+// Hidden books behave as if they did not exist, so callers turn a denial
+// into a 404.
+pub async fn ensure_access(username: &str, hash: &str, state: &AppState) -> Result<(), AppError> {
+    let is_admin = user_repo::find_by_username(&state.db, username)
+        .await?
+        .is_some_and(|user| user.is_admin);
+
+    if is_admin || group_repo::can_access(&state.db, hash, username).await? {
+        Ok(())
+    } else {
+        Err(AppError::NotFound)
+    }
+}
+
+async fn ensure_group(name: &str, state: &AppState) -> Result<(), AppError> {
+    group_repo::find_by_name(&state.db, name)
+        .await?
+        .map(|_| ())
+        .ok_or(AppError::NotFound)
+}
+
+async fn group_info(name: String, state: &AppState) -> Result<GroupInfo, AppError> {
+    Ok(GroupInfo {
+        members: group_repo::find_members(&state.db, &name).await?,
+        audiobooks: group_repo::find_audiobooks(&state.db, &name).await?,
+        name,
+    })
+}
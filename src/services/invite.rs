@@ -0,0 +1,98 @@
+use crate::{
+    error::AppError,
+    inputs::invite::InviteCreate,
+    models::{
+        audit::{ACTION_INVITE_CREATE, ACTION_INVITE_DELETE},
+        invite::Invite,
+    },
+    outputs::invite::{InviteCreated, InviteInfo},
+    repo::invite as invite_repo,
+    services::audit,
+    services::auth::{add_ttl, generate_token, hash_token},
+    state::AppState,
+};
+use chrono::Utc;
+use std::time::Duration;
+
+const MAX_EXPIRES_IN_HOURS: u64 = 3650 * 24;
+
+pub async fn list_invites(state: &AppState) -> Result<Vec<InviteInfo>, AppError> {
+    let invites = invite_repo::find_all(&state.db).await?;
+
+    Ok(invites
+        .into_iter()
+        .map(|invite| InviteInfo {
+            id: invite.id,
+            created_by: invite.created_by,
+            max_uses: invite.max_uses,
+            uses: invite.uses,
+            created_at: invite.created_at.and_utc().timestamp(),
+            expires_at: invite.expires_at.map(|t| t.and_utc().timestamp()),
+        })
+        .collect())
+}
+
+pub async fn create_invite(
+    username: String,
+    input: InviteCreate,
+    state: &AppState,
+) -> Result<InviteCreated, AppError> {
+    let max_uses = input.max_uses.unwrap_or(1);
+    if max_uses < 1 {
+        return Err(AppError::BadRequest("max_uses must be at least 1".into()));
+    }
+    if input.expires_in_hours.is_some_and(|hours| hours > MAX_EXPIRES_IN_HOURS) {
+        return Err(AppError::BadRequest(format!(
+            "expires_in_hours must be at most {MAX_EXPIRES_IN_HOURS}"
+        )));
+    }
+
+    let now = Utc::now().naive_utc();
+    let expires_at = input
+        .expires_in_hours
+        .map(|hours| add_ttl(now, Duration::from_secs(hours * 3600)))
+        .transpose()?;
+
+    let code = generate_token();
+    let mut invite = Invite {
+        id: 0,
+        code_hash: hash_token(&code),
+        created_by: username,
+        max_uses,
+        uses: 0,
+        created_at: now,
+        expires_at,
+    };
+    invite.id = invite_repo::create(&state.db, &invite).await?;
+    tracing::info!("{} created invite {} ({} uses)", invite.created_by, invite.id, max_uses);
+    audit::record(
+        state,
+        Some(&invite.created_by),
+        ACTION_INVITE_CREATE,
+        Some(&invite.id.to_string()),
+        Some(format!("{max_uses} uses")),
+    )
+    .await;
+
+    Ok(InviteCreated {
+        id: invite.id,
+        code,
+        max_uses,
+        expires_at: invite.expires_at.map(|t| t.and_utc().timestamp()),
+    })
+}
+
+pub async fn delete_invite(actor: String, id: i64, state: &AppState) -> Result<(), AppError> {
+    if invite_repo::delete(&state.db, id).await? == 0 {
+        return Err(AppError::NotFound);
+    }
+    audit::record(
+        state,
+        Some(&actor),
+        ACTION_INVITE_DELETE,
+        Some(&id.to_string()),
+        None,
+    )
+    .await;
+    Ok(())
+}
@@ -1,48 +1,302 @@
 use crate::{
     error::AppError,
-    models::audiobook::Audiobook,
-    repo::{audiobook as audiobook_repo, position as position_repo},
-    state::AppState,
+    models::{
+        audiobook::Audiobook,
+        audit::{ACTION_LIBRARY_CLEANUP, ACTION_LIBRARY_PURGE, ACTION_LIBRARY_SCAN},
+        chapter_cache::CachedChapter,
+    },
+    outputs::library::{
+        BookReport, DuplicateHash, LibraryInfo, PurgeResult, ReportJobInfo, ScanErrorInfo,
+        ScanJobInfo, ScanReport, StaleBook,
+    },
+    repo::{
+        audiobook as audiobook_repo, chapter_cache as chapter_cache_repo,
+        position as position_repo,
+    },
+    services::{audiobook::visible_audiobooks, audit},
+    state::{AppState, ReportJob, ScanError, ScanJob, ScanState},
 };
-use chrono::Utc;
+use chrono::{NaiveDateTime, Utc};
 use flate2::write::GzEncoder;
 use flate2::Compression;
 use serde_yml::Value;
 use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
 use std::fmt::Write as FmtWrite;
 use std::fs;
 use std::path::Path;
-use std::time::Duration;
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
 use std::{fs::File, path::PathBuf};
 use symphonia::core::{
     formats::FormatOptions, io::MediaSourceStream, meta::MetadataOptions, probe::Hint,
 };
 use tar::Builder;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
 
+// Finished scan jobs kept around for GET /library/scan/{id}.
+const SCAN_JOB_HISTORY: usize = 20;
+
+// Finished reports kept around for GET /library/report/{id}. Reports list every
+// book, so fewer are kept than scan jobs.
+const REPORT_JOB_HISTORY: usize = 5;
+
+// Runs a scan to completion in the caller, used on startup.
 pub async fn scan_library(state: &AppState) -> Result<(), AppError> {
-    let dirs: Vec<PathBuf> = fs::read_dir(&state.library_path)
-        .map(|entries| {
-            entries
-                .flatten()
-                .map(|e| e.path())
-                .filter(|p| p.is_dir())
-                .collect()
-        })
-        .unwrap_or_default();
+    match claim_scan_job(state) {
+        Ok(id) => run_scan_job(state, id, None).await,
+        Err(_) => Ok(()),
+    }
+}
 
-    let total_chapters: usize = dirs.iter().map(|d| count_chapters(d)).sum();
+// Rescans only the given directories, used by the library watcher. Returns
+// false without scanning if another scan is running, so the caller can retry.
+pub async fn rescan_dirs(state: &AppState, dirs: &[PathBuf]) -> Result<bool, AppError> {
+    match claim_scan_job(state) {
+        Ok(id) => run_scan_job(state, id, Some(dirs)).await.map(|()| true),
+        Err(_) => Ok(false),
+    }
+}
 
-    let existing_map = audiobook_repo::find_hashes_with_checksums(&state.db).await?;
+// Starts a scan in the background. If one is already running the request is
+// coalesced into it and the running job is returned instead.
+pub async fn request_scan(actor: String, state: &AppState) -> Result<ScanJobInfo, AppError> {
+    let id = match claim_scan_job(state) {
+        Ok(id) => {
+            let job_state = state.clone();
+            tokio::spawn(async move {
+                if let Err(e) = run_scan_job(&job_state, id, None).await {
+                    tracing::error!("library scan {} failed: {}", id, e);
+                }
+            });
+            audit::record(state, Some(&actor), ACTION_LIBRARY_SCAN, None, Some(format!("job {id}")))
+                .await;
+            id
+        }
+        Err(id) => id,
+    };
+    get_scan_job(id, state)
+}
+
+pub fn get_scan_job(id: u64, state: &AppState) -> Result<ScanJobInfo, AppError> {
+    let jobs = state.scan_jobs.lock().unwrap();
+    let job = jobs.jobs.iter().find(|j| j.id == id).ok_or(AppError::NotFound)?;
+    Ok(ScanJobInfo {
+        id: job.id,
+        state: job.state.as_str(),
+        started_at: job.started_at.and_utc().timestamp(),
+        finished_at: job.finished_at.map(|t| t.and_utc().timestamp()),
+        books_total: job.books_total,
+        books_processed: job.books_processed,
+        chapters_total: job.chapters_total,
+        chapters_processed: job.chapters_processed,
+        errors: job
+            .errors
+            .iter()
+            .map(|e| ScanErrorInfo {
+                path: e.path.clone(),
+                message: e.message.clone(),
+            })
+            .collect(),
+        error: job.error.clone(),
+    })
+}
+
+// Registers a new running job, or returns the id of the one already running.
+fn claim_scan_job(state: &AppState) -> Result<u64, u64> {
+    let mut jobs = state.scan_jobs.lock().unwrap();
+    if let Some(running) = jobs.jobs.iter().find(|j| j.state == ScanState::Running) {
+        return Err(running.id);
+    }
+    let id = jobs.next_id;
+    jobs.next_id += 1;
+    jobs.jobs.push_back(ScanJob {
+        id,
+        state: ScanState::Running,
+        started_at: Utc::now().naive_utc(),
+        finished_at: None,
+        books_total: 0,
+        books_processed: 0,
+        chapters_total: 0,
+        chapters_processed: 0,
+        errors: Vec::new(),
+        error: None,
+    });
+    while jobs.jobs.len() > SCAN_JOB_HISTORY {
+        jobs.jobs.pop_front();
+    }
+    Ok(id)
+}
+
+fn update_scan_job(state: &AppState, id: u64, f: impl FnOnce(&mut ScanJob)) {
+    let mut jobs = state.scan_jobs.lock().unwrap();
+    if let Some(job) = jobs.jobs.iter_mut().find(|j| j.id == id) {
+        f(job);
+    }
+}
+
+async fn run_scan_job(state: &AppState, id: u64, dirs: Option<&[PathBuf]>) -> Result<(), AppError> {
+    let result = scan(state, id, dirs).await;
+    update_scan_job(state, id, |job| {
+        job.finished_at = Some(Utc::now().naive_utc());
+        match &result {
+            Ok(()) => job.state = ScanState::Finished,
+            Err(e) => {
+                job.state = ScanState::Failed;
+                job.error = Some(e.to_string());
+            }
+        }
+    });
+    result
+}
+
+// Without a scope every library is scanned and books that were not found are
+// removed. With a scope only books in or around those directories are scanned,
+// and only books inside the scanned areas are removed.
+async fn scan(state: &AppState, job: u64, scope: Option<&[PathBuf]>) -> Result<(), AppError> {
+    let mut dirs: Vec<(&str, PathBuf)> = Vec::new();
+    let mut regions: Vec<PathBuf> = Vec::new();
+    match scope {
+        None => dirs = find_all_books(state),
+        Some(scope) => {
+            for dir in scope {
+                let Some(library) = state.libraries.iter().find(|l| dir.starts_with(&l.path)) else {
+                    continue;
+                };
+                let (region, books) = find_affected_books(&library.path, dir, state.scan_depth);
+                for book in books {
+                    if !dirs.iter().any(|(_, d)| d == &book) {
+                        dirs.push((library.name.as_str(), book));
+                    }
+                }
+                regions.push(region);
+            }
+        }
+    }
+    let in_scope = |path: &str| scope.is_none() || regions.iter().any(|r| Path::new(path).starts_with(r));
+
+    let chapter_files: Vec<PathBuf> = dirs.iter().flat_map(|(_, d)| chapter_files(d)).collect();
+    update_scan_job(state, job, |job| {
+        job.books_total = dirs.len();
+        job.chapters_total = chapter_files.len();
+    });
+
+    let existing_books = audiobook_repo::find_all(&state.db).await?;
+    if let Some(message) = empty_library(state, &dirs, scope.map(|_| regions.as_slice()), &existing_books) {
+        return Err(AppError::Internal(message));
+    }
+
+    let mut existing_map = audiobook_repo::find_hashes_with_checksums(&state.db).await?;
+    let stored: HashMap<String, CachedChapter> = chapter_cache_repo::find_all(&state.db)
+        .await?
+        .into_iter()
+        .map(|c| (c.path.clone(), c))
+        .collect();
+    let mut cache = stored.clone();
+    probe_chapters(state, Some(job), chapter_files, &mut cache).await;
+    let cache = Arc::new(cache);
 
     let mut scanned: Vec<(Audiobook, String)> = Vec::new();
-    let mut chapter_offset = 0usize;
-    for dir in &dirs {
-        match scan_audiobook(dir, chapter_offset, total_chapters) {
-            Ok((audiobook, n, checksum)) => {
-                chapter_offset += n;
+    let mut chapters: Vec<CachedChapter> = Vec::new();
+    let mut failed: HashMap<String, String> = HashMap::new();
+    for (library, dir) in &dirs {
+        let (book_dir, library, book_cache) = (dir.clone(), library.to_string(), cache.clone());
+        let result = tokio::task::spawn_blocking(move || scan_audiobook(&book_dir, &library, &book_cache))
+            .await
+            .unwrap_or_else(|e| Err(AppError::Internal(format!("scan task panicked: {e}"))))
+            .and_then(|book| {
+                // Only the first directory with a given hash is stored.
+                match scanned.iter().find(|(a, _)| a.hash == book.0.hash) {
+                    Some((first, _)) => Err(AppError::Internal(format!(
+                        "duplicate hash {}, already used by {}",
+                        first.hash, first.path
+                    ))),
+                    None => Ok(book),
+                }
+            });
+        match result {
+            Ok((audiobook, checksum, book_chapters)) => {
                 scanned.push((audiobook, checksum));
+                chapters.extend(book_chapters);
+                update_scan_job(state, job, |job| job.books_processed += 1);
+            }
+            Err(e) => {
+                tracing::error!("scan failed for {:?}: {}", dir, e);
+                failed.insert(dir.to_string_lossy().into_owned(), e.to_string());
+                update_scan_job(state, job, |job| {
+                    job.books_processed += 1;
+                    job.errors.push(ScanError {
+                        path: dir.to_string_lossy().into_owned(),
+                        message: e.to_string(),
+                    });
+                });
+            }
+        }
+    }
+
+    // Only chapters of books found in this scan are kept in the cache.
+    let mut cached_paths: HashSet<&str> = HashSet::new();
+    for chapter in &chapters {
+        if stored.get(&chapter.path) != Some(chapter) {
+            chapter_cache_repo::upsert(&state.db, chapter).await?;
+        }
+        cached_paths.insert(&chapter.path);
+    }
+    for path in stored.keys() {
+        if !cached_paths.contains(path.as_str()) && in_scope(path) {
+            chapter_cache_repo::delete(&state.db, path).await?;
+        }
+    }
+
+    let now = Utc::now().naive_utc();
+    let scanned_hashes: HashSet<&str> = scanned.iter().map(|(a, _)| a.hash.as_str()).collect();
+    let scanned_paths: HashSet<&str> = scanned.iter().map(|(a, _)| a.path.as_str()).collect();
+
+    // A known directory whose book got a new hash is the same book with edited
+    // metadata: it moves to the new hash and the old one stays as an alias.
+    let mut renamed: HashSet<&str> = HashSet::new();
+    for (audiobook, _) in &scanned {
+        if existing_map.contains_key(&audiobook.hash) {
+            continue;
+        }
+        let Some(old) = existing_books
+            .iter()
+            .find(|b| b.path == audiobook.path && !scanned_hashes.contains(b.hash.as_str()))
+        else {
+            continue;
+        };
+        tracing::info!(old = %old.hash, new = %audiobook.hash, path = %audiobook.path, "book hash changed");
+        audiobook_repo::change_hash(&state.db, &old.hash, &audiobook.hash, now).await?;
+        if let Some(entry) = existing_map.remove(&old.hash) {
+            existing_map.insert(audiobook.hash.clone(), entry);
+        }
+        renamed.insert(&old.hash);
+    }
+
+    // Stale books go first: a book at a path that another scanned book now
+    // claims must be gone before that book is stored, as paths are unique.
+    for book in &existing_books {
+        if scanned_hashes.contains(book.hash.as_str())
+            || renamed.contains(book.hash.as_str())
+            || !in_scope(&book.path)
+        {
+            continue;
+        }
+        match missing_action(state, book, &scanned_paths, now) {
+            MissingAction::Delete => {
+                tracing::info!(hash = %book.hash, path = %book.path, "removing book");
+                audiobook_repo::delete(&state.db, &book.hash).await?;
             }
-            Err(e) => tracing::error!("scan failed for {:?}: {}", dir, e),
+            MissingAction::Flag => {
+                let reason = failed
+                    .get(&book.path)
+                    .map(String::as_str)
+                    .unwrap_or("directory not found");
+                tracing::warn!(hash = %book.hash, path = %book.path, reason, "book missing");
+                audiobook_repo::mark_missing(&state.db, &book.hash, now, reason).await?;
+            }
+            MissingAction::Keep => {}
         }
     }
 
@@ -88,21 +342,378 @@ pub async fn scan_library(state: &AppState) -> Result<(), AppError> {
         }
     }
 
-    let scanned_hashes: Vec<&str> = scanned.iter().map(|(a, _)| a.hash.as_str()).collect();
-    for hash in existing_map.keys() {
-        if !scanned_hashes.contains(&hash.as_str()) {
-            audiobook_repo::delete(&state.db, hash).await?;
+    Ok(())
+}
+
+// Starts a report in the background. If one is already running the request is
+// coalesced into it and the running job is returned instead.
+pub fn request_report(state: &AppState) -> Result<ReportJobInfo, AppError> {
+    let id = {
+        let mut jobs = state.report_jobs.lock().unwrap();
+        match jobs.jobs.iter().find(|j| j.state == ScanState::Running) {
+            Some(running) => running.id,
+            None => {
+                let id = jobs.next_id;
+                jobs.next_id += 1;
+                jobs.jobs.push_back(ReportJob {
+                    id,
+                    state: ScanState::Running,
+                    started_at: Utc::now().naive_utc(),
+                    finished_at: None,
+                    report: None,
+                    error: None,
+                });
+                while jobs.jobs.len() > REPORT_JOB_HISTORY {
+                    jobs.jobs.pop_front();
+                }
+                let job_state = state.clone();
+                tokio::spawn(async move { run_report_job(&job_state, id).await });
+                id
+            }
         }
+    };
+    get_report_job(id, state)
+}
+
+pub fn get_report_job(id: u64, state: &AppState) -> Result<ReportJobInfo, AppError> {
+    let jobs = state.report_jobs.lock().unwrap();
+    let job = jobs.jobs.iter().find(|j| j.id == id).ok_or(AppError::NotFound)?;
+    Ok(ReportJobInfo {
+        id: job.id,
+        state: job.state.as_str(),
+        started_at: job.started_at.and_utc().timestamp(),
+        finished_at: job.finished_at.map(|t| t.and_utc().timestamp()),
+        report: job.report.clone(),
+        error: job.error.clone(),
+    })
+}
+
+async fn run_report_job(state: &AppState, id: u64) {
+    let result = scan_report(state).await;
+    if let Err(e) = &result {
+        tracing::error!("library report {} failed: {}", id, e);
     }
+    let mut jobs = state.report_jobs.lock().unwrap();
+    if let Some(job) = jobs.jobs.iter_mut().find(|j| j.id == id) {
+        job.finished_at = Some(Utc::now().naive_utc());
+        match result {
+            Ok(report) => {
+                job.state = ScanState::Finished;
+                job.report = Some(report);
+            }
+            Err(e) => {
+                job.state = ScanState::Failed;
+                job.error = Some(e.to_string());
+            }
+        }
+    }
+}
+
+// Dry run of a full scan: reports what a scan would do and every problem found
+// in the book directories. Nothing is written to the database, newly probed
+// chapter durations are kept in memory for this report only.
+async fn scan_report(state: &AppState) -> Result<ScanReport, AppError> {
+    let dirs = find_all_books(state);
+    let chapter_files: Vec<PathBuf> = dirs.iter().flat_map(|(_, d)| chapter_files(d)).collect();
+    let mut cache: HashMap<String, CachedChapter> = chapter_cache_repo::find_all(&state.db)
+        .await?
+        .into_iter()
+        .map(|c| (c.path.clone(), c))
+        .collect();
+    let failures = Arc::new(probe_chapters(state, None, chapter_files, &mut cache).await);
+    let cache = Arc::new(cache);
+
+    let existing_books = audiobook_repo::find_all(&state.db).await?;
+    let error = empty_library(state, &dirs, None, &existing_books);
+    let existing: HashMap<&str, &Audiobook> =
+        existing_books.iter().map(|b| (b.hash.as_str(), b)).collect();
+
+    let mut books = Vec::new();
+    let mut paths_by_hash: HashMap<String, Vec<String>> = HashMap::new();
+    let mut renamed_paths: HashSet<String> = HashSet::new();
+    for (library, dir) in &dirs {
+        let (book_dir, library, cache, failures) =
+            (dir.clone(), library.to_string(), cache.clone(), failures.clone());
+        let (library, result) = tokio::task::spawn_blocking(move || {
+            let problems = check_book(&book_dir, &failures);
+            let result = if problems.is_empty() {
+                scan_audiobook(&book_dir, &library, &cache).map_err(|e| vec![e.to_string()])
+            } else {
+                Err(problems)
+            };
+            (library, result)
+        })
+        .await
+        .map_err(|e| AppError::Internal(format!("scan task panicked: {e}")))?;
 
+        let path = dir.to_string_lossy().into_owned();
+        books.push(match result {
+            Ok((audiobook, _, _)) if paths_by_hash.contains_key(&audiobook.hash) => {
+                let paths = paths_by_hash.get_mut(&audiobook.hash).unwrap();
+                let problem = format!("duplicate hash {}, already used by {}", audiobook.hash, paths[0]);
+                paths.push(path.clone());
+                BookReport {
+                    path,
+                    library,
+                    hash: Some(audiobook.hash),
+                    title: Some(audiobook.title),
+                    action: "skip",
+                    problems: vec![problem],
+                }
+            }
+            Ok((audiobook, _, _)) => {
+                paths_by_hash.insert(audiobook.hash.clone(), vec![path.clone()]);
+                let action = match existing.get(audiobook.hash.as_str()) {
+                    None if existing_books.iter().any(|b| b.path == path) => {
+                        renamed_paths.insert(path.clone());
+                        "rename"
+                    }
+                    None => "create",
+                    Some(current) if same_metadata(current, &audiobook) => "unchanged",
+                    Some(_) => "update",
+                };
+                BookReport {
+                    path,
+                    library,
+                    hash: Some(audiobook.hash),
+                    title: Some(audiobook.title),
+                    action,
+                    problems: Vec::new(),
+                }
+            }
+            Err(problems) => BookReport {
+                path,
+                library,
+                hash: None,
+                title: None,
+                action: "skip",
+                problems,
+            },
+        });
+    }
+
+    let now = Utc::now().naive_utc();
+    let scanned_paths: HashSet<&str> = paths_by_hash.values().map(|p| p[0].as_str()).collect();
+    let mut missing = Vec::new();
+    let mut deleted = Vec::new();
+    for book in existing_books
+        .iter()
+        .filter(|b| !paths_by_hash.contains_key(&b.hash) && !renamed_paths.contains(&b.path))
+    {
+        let stale = StaleBook {
+            hash: book.hash.clone(),
+            title: book.title.clone(),
+            path: book.path.clone(),
+        };
+        match missing_action(state, book, &scanned_paths, now) {
+            MissingAction::Delete => deleted.push(stale),
+            MissingAction::Flag | MissingAction::Keep => missing.push(stale),
+        }
+    }
+    missing.sort_by(|a, b| a.path.cmp(&b.path));
+    deleted.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let mut duplicates: Vec<DuplicateHash> = paths_by_hash
+        .into_iter()
+        .filter(|(_, paths)| paths.len() > 1)
+        .map(|(hash, paths)| DuplicateHash { hash, paths })
+        .collect();
+    duplicates.sort_by(|a, b| a.hash.cmp(&b.hash));
+
+    Ok(ScanReport {
+        error,
+        books,
+        missing,
+        deleted,
+        duplicates,
+    })
+}
+
+// Counts only the books the caller is allowed to see.
+pub async fn list_libraries(username: String, state: &AppState) -> Result<Vec<LibraryInfo>, AppError> {
+    let audiobooks = visible_audiobooks(&username, state).await?;
+    Ok(state
+        .libraries
+        .iter()
+        .map(|library| LibraryInfo {
+            name: library.name.clone(),
+            audiobooks: audiobooks.iter().filter(|b| b.library == library.name).count(),
+        })
+        .collect())
+}
+
+pub async fn cleanup(actor: String, state: &AppState) -> Result<(), AppError> {
+    run_cleanup(Some(&actor), state).await?;
     Ok(())
 }
 
-pub async fn cleanup(state: &AppState) -> Result<(), AppError> {
+// Returns a summary of what was removed. Without an actor the cleanup was
+// started by the scheduler.
+pub async fn run_cleanup(actor: Option<&str>, state: &AppState) -> Result<String, AppError> {
     let cutoff_date = Utc::now().naive_utc() - chrono::Duration::days(3 * 365);
-    position_repo::delete_old(&state.db, cutoff_date).await?;
-    position_repo::delete_beyond_final(&state.db).await?;
-    Ok(())
+    let old = position_repo::delete_old(&state.db, cutoff_date).await?;
+    let finished = position_repo::delete_beyond_final(&state.db).await?;
+    let summary = format!("{old} old positions, {finished} finished positions");
+    audit::record(state, actor, ACTION_LIBRARY_CLEANUP, None, Some(summary.clone())).await;
+    Ok(summary)
+}
+
+// Removes every missing book now instead of waiting for the grace period.
+pub async fn purge_missing(actor: String, state: &AppState) -> Result<PurgeResult, AppError> {
+    let mut purged = Vec::new();
+    for book in audiobook_repo::find_all(&state.db).await? {
+        if book.missing_since.is_some() {
+            audiobook_repo::delete(&state.db, &book.hash).await?;
+            purged.push(book.hash);
+        }
+    }
+    audit::record(
+        state,
+        Some(&actor),
+        ACTION_LIBRARY_PURGE,
+        None,
+        Some(format!("{} missing books", purged.len())),
+    )
+    .await;
+    Ok(PurgeResult {
+        purged: purged.len(),
+    })
+}
+
+// Full scan for the scheduler. A scan that is already running counts as this
+// run instead of starting a second one.
+pub async fn scheduled_scan(state: &AppState) -> Result<String, AppError> {
+    match claim_scan_job(state) {
+        Ok(id) => {
+            run_scan_job(state, id, None).await?;
+            let job = get_scan_job(id, state)?;
+            Ok(format!("job {id}, {} books, {} errors", job.books_total, job.errors.len()))
+        }
+        Err(id) => Ok(format!("skipped, job {id} was already running")),
+    }
+}
+
+enum MissingAction {
+    Flag,
+    Keep,
+    Delete,
+}
+
+// A book that was not found is flagged as missing and kept with its positions
+// until the grace period is over, so a broken info file or an unmounted disk
+// does not lose it. It is removed right away if another book now lives in its
+// directory.
+fn missing_action(
+    state: &AppState,
+    book: &Audiobook,
+    scanned_paths: &HashSet<&str>,
+    now: NaiveDateTime,
+) -> MissingAction {
+    if scanned_paths.contains(book.path.as_str()) {
+        return MissingAction::Delete;
+    }
+    match book.missing_since {
+        None if state.missing_grace_days == 0 => MissingAction::Delete,
+        None => MissingAction::Flag,
+        Some(since) if now - since >= chrono::Duration::days(state.missing_grace_days as i64) => {
+            MissingAction::Delete
+        }
+        Some(_) => MissingAction::Keep,
+    }
+}
+
+// A library root without any books while the database still has books for it
+// is most likely unmounted or unreadable. Scanning it would flag the whole
+// library as missing, so the scan is refused instead.
+fn empty_library(
+    state: &AppState,
+    dirs: &[(&str, PathBuf)],
+    regions: Option<&[PathBuf]>,
+    existing: &[Audiobook],
+) -> Option<String> {
+    state
+        .libraries
+        .iter()
+        .filter(|l| regions.is_none_or(|regions| regions.contains(&l.path)))
+        .filter(|l| !dirs.iter().any(|(name, _)| *name == l.name))
+        .find(|l| existing.iter().any(|b| b.library == l.name && b.missing_since.is_none()))
+        .map(|l| format!("library {} at {:?} is empty or unreadable, scan aborted", l.name, l.path))
+}
+
+fn find_all_books(state: &AppState) -> Vec<(&str, PathBuf)> {
+    let mut dirs = Vec::new();
+    for library in &state.libraries {
+        let entries = find_book_dirs(&library.path, state.scan_depth).unwrap_or_else(|e| {
+            tracing::error!("cannot read library {} at {:?}: {}", library.name, library.path, e);
+            Vec::new()
+        });
+        dirs.extend(entries.into_iter().map(|dir| (library.name.as_str(), dir)));
+    }
+    dirs
+}
+
+// Walks the library up to max_depth levels below the root. Any directory with
+// an info file is a book and is not descended into; everything else is only
+// traversed. Directories are tracked by canonical path so symlink loops and
+// links to an already visited directory are skipped.
+fn find_book_dirs(root: &Path, max_depth: usize) -> std::io::Result<Vec<PathBuf>> {
+    let mut visited = HashSet::new();
+    visited.insert(fs::canonicalize(root)?);
+    let mut stack: Vec<(PathBuf, usize)> = vec![(root.to_path_buf(), 0)];
+    let mut books = Vec::new();
+
+    while let Some((dir, depth)) = stack.pop() {
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if depth == 0 => return Err(e),
+            Err(e) => {
+                tracing::warn!("cannot read {:?}: {}", dir, e);
+                continue;
+            }
+        };
+        for path in entries.flatten().map(|e| e.path()).filter(|p| p.is_dir()) {
+            let canonical = match fs::canonicalize(&path) {
+                Ok(canonical) => canonical,
+                Err(e) => {
+                    tracing::warn!("cannot resolve {:?}: {}", path, e);
+                    continue;
+                }
+            };
+            if !visited.insert(canonical) {
+                tracing::warn!("skipping {:?}: directory already visited", path);
+                continue;
+            }
+            if get_info_path(&path).is_ok() {
+                books.push(path);
+            } else if depth + 1 < max_depth {
+                stack.push((path, depth + 1));
+            }
+        }
+    }
+
+    books.sort();
+    Ok(books)
+}
+
+// Maps a changed directory to the books that need a rescan. Inside a book that
+// is the book itself; above books it is every book below the directory. The
+// returned region is the part of the library the result is authoritative for.
+fn find_affected_books(root: &Path, dir: &Path, max_depth: usize) -> (PathBuf, Vec<PathBuf>) {
+    for ancestor in dir.ancestors().take_while(|a| *a != root) {
+        if get_info_path(ancestor).is_ok() {
+            return (ancestor.to_path_buf(), vec![ancestor.to_path_buf()]);
+        }
+    }
+    let depth = dir.strip_prefix(root).map(|p| p.components().count()).unwrap_or(0);
+    let books = if dir.is_dir() && depth < max_depth {
+        find_book_dirs(dir, max_depth - depth).unwrap_or_else(|e| {
+            tracing::warn!("cannot read {:?}: {}", dir, e);
+            Vec::new()
+        })
+    } else {
+        Vec::new()
+    };
+    (dir.to_path_buf(), books)
 }
 
 struct AudiobookChapter {
@@ -110,30 +721,182 @@ struct AudiobookChapter {
     pub path: String,
 }
 
-fn count_chapters(dir: &Path) -> usize {
+fn chapter_files(dir: &Path) -> Vec<PathBuf> {
     let info_path = match get_info_path(dir) {
         Ok(p) => p,
-        Err(_) => return 0,
+        Err(_) => return Vec::new(),
     };
     let data = match fs::read_to_string(&info_path) {
         Ok(d) => d,
-        Err(_) => return 0,
+        Err(_) => return Vec::new(),
     };
     let yaml: Value = match serde_yml::from_str(&data) {
         Ok(v) => v,
-        Err(_) => return 0,
+        Err(_) => return Vec::new(),
     };
     yaml["chapters"]
         .as_sequence()
-        .map(|s| s.len())
-        .unwrap_or(0)
+        .map(|s| s.iter().filter_map(|c| c["path"].as_str()).map(|p| dir.join(p)).collect())
+        .unwrap_or_default()
+}
+
+// Probes every chapter that is missing from the cache or changed on disk and
+// adds the result to the cache. Probing runs on the blocking thread pool with
+// at most scan_parallelism files at a time. Files that could not be probed are
+// returned with the error; scan_audiobook reports them again for the book.
+async fn probe_chapters(
+    state: &AppState,
+    job: Option<u64>,
+    files: Vec<PathBuf>,
+    cache: &mut HashMap<String, CachedChapter>,
+) -> HashMap<String, String> {
+    let total = files.len();
+    let mut done = 0;
+    let semaphore = Arc::new(Semaphore::new(state.scan_parallelism));
+    let mut tasks = JoinSet::new();
+    for path in files {
+        let Ok((size, mtime_ns)) = chapter_stat(&path) else {
+            done += 1;
+            continue;
+        };
+        let key = path.to_string_lossy().into_owned();
+        if cache.get(&key).is_some_and(|c| c.size == size && c.mtime_ns == mtime_ns) {
+            done += 1;
+            continue;
+        }
+        let semaphore = semaphore.clone();
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            let probe_path = path.clone();
+            let result = tokio::task::spawn_blocking(move || compute_audio_duration(&probe_path))
+                .await
+                .unwrap_or_else(|e| Err(AppError::Internal(format!("probe task panicked: {e}"))));
+            (path, key, size, mtime_ns, result)
+        });
+    }
+    let mut failures = HashMap::new();
+    let report_progress = |done: usize| {
+        if let Some(job) = job {
+            update_scan_job(state, job, |job| job.chapters_processed = done);
+        }
+    };
+    report_progress(done);
+
+    while let Some(joined) = tasks.join_next().await {
+        done += 1;
+        report_progress(done);
+        let Ok((path, key, size, mtime_ns, result)) = joined else {
+            continue;
+        };
+        match result {
+            Ok(duration) => {
+                tracing::info!("[{}/{}] read duration: {:?}", done, total, path);
+                cache.insert(
+                    key.clone(),
+                    CachedChapter {
+                        path: key,
+                        size,
+                        mtime_ns,
+                        duration_us: duration.as_micros() as i64,
+                    },
+                );
+            }
+            Err(e) => {
+                tracing::warn!("[{}/{}] cannot read duration of {:?}: {}", done, total, path, e);
+                failures.insert(key, e.to_string());
+            }
+        }
+    }
+    failures
+}
+
+// Collects every problem with a book directory, where scan_audiobook stops at
+// the first one. Audio files are not probed again, failed probes are looked up
+// in failures.
+fn check_book(dir: &Path, failures: &HashMap<String, String>) -> Vec<String> {
+    let info_path = match get_info_path(dir) {
+        Ok(p) => p,
+        Err(e) => return vec![e.to_string()],
+    };
+    let yaml: Value = match fs::read_to_string(&info_path)
+        .map_err(|e| e.to_string())
+        .and_then(|data| serde_yml::from_str(&data).map_err(|e| e.to_string()))
+    {
+        Ok(v) => v,
+        Err(e) => return vec![format!("failed to parse YAML from {:?}: {e}", info_path)],
+    };
+
+    let mut problems = Vec::new();
+    for field in ["title", "author", "description"] {
+        if yaml[field].as_str().is_none() {
+            problems.push(format!("missing or invalid '{field}'"));
+        }
+    }
+    if yaml["date"].as_i64().is_none() {
+        problems.push("missing or invalid 'date'".to_string());
+    }
+    if yaml["genres"].as_sequence().is_none() {
+        problems.push("missing or invalid 'genres'".to_string());
+    }
+    if let Err(e) = parse_id(&yaml["id"]) {
+        problems.push(e);
+    }
+    let Some(chapters) = yaml["chapters"].as_sequence() else {
+        problems.push("missing or invalid 'chapters'".to_string());
+        return problems;
+    };
+    for (i, chapter) in chapters.iter().enumerate() {
+        if chapter["title"].as_str().is_none() {
+            problems.push(format!("chapter {} missing 'title'", i + 1));
+        }
+        let Some(path) = chapter["path"].as_str() else {
+            problems.push(format!("chapter {} missing 'path'", i + 1));
+            continue;
+        };
+        let full_path = dir.join(path);
+        if !full_path.exists() {
+            problems.push(format!("chapter file does not exist: {path}"));
+        } else if let Some(e) = failures.get(full_path.to_string_lossy().as_ref()) {
+            problems.push(format!("unreadable audio in {path}: {e}"));
+        }
+    }
+    problems
+}
+
+// Whether a scan would leave the stored book as it is.
+fn same_metadata(current: &Audiobook, scanned: &Audiobook) -> bool {
+    current.missing_since.is_none()
+        && current.title == scanned.title
+        && current.author == scanned.author
+        && current.date == scanned.date
+        && current.description == scanned.description
+        && current.genres == scanned.genres
+        && current.duration == scanned.duration
+        && current.size == scanned.size
+        && current.path == scanned.path
+        && current.final_chapter_index == scanned.final_chapter_index
+        && current.final_chapter_position == scanned.final_chapter_position
+        && current.cover == scanned.cover
+        && current.library == scanned.library
+}
+
+// Size and modification time identify an unchanged chapter file.
+fn chapter_stat(path: &Path) -> Result<(i64, i64), AppError> {
+    let metadata = fs::metadata(path)?;
+    let mtime_ns = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos() as i64)
+        .unwrap_or(0);
+    Ok((metadata.len() as i64, mtime_ns))
 }
 
 fn scan_audiobook(
     dir: &Path,
-    chapter_offset: usize,
-    total_chapters: usize,
-) -> Result<(Audiobook, usize, String), AppError> {
+    library: &str,
+    cache: &HashMap<String, CachedChapter>,
+) -> Result<(Audiobook, String, Vec<CachedChapter>), AppError> {
     let info_path = get_info_path(dir)?;
 
     let data = fs::read_to_string(&info_path)
@@ -174,8 +937,11 @@ fn scan_audiobook(
 
     let cover = yaml["cover"].as_str().map(|s| s.to_string());
 
+    let id = parse_id(&yaml["id"]).map_err(AppError::Internal)?;
+
     let mut chapter_paths: Vec<PathBuf> = Vec::new();
     let mut chapter_durations: Vec<Duration> = Vec::new();
+    let mut cached_chapters: Vec<CachedChapter> = Vec::new();
     let mut total_duration = Duration::new(0, 0);
     let mut total_size = fs::metadata(&info_path)?.len();
     if let Some(ref cover_file) = cover {
@@ -184,7 +950,7 @@ fn scan_audiobook(
     let mut final_chapter_index = 0;
     let mut final_chapter_position = 0;
 
-    for (i, chapter) in chapters.iter().enumerate() {
+    for chapter in chapters {
         let chapter_title = chapter["title"]
             .as_str()
             .ok_or_else(|| AppError::Internal("chapter missing 'title'".into()))?;
@@ -202,20 +968,35 @@ fn scan_audiobook(
             )));
         }
 
-        tracing::info!(
-            "[{}/{}] reading duration: {}",
-            chapter_offset + i + 1,
-            total_chapters,
-            chapter_title
-        );
+        // Reuse the probed duration while the file's size and mtime are unchanged.
+        let (size, mtime_ns) = chapter_stat(&full_chapter_path)?;
+        let cache_key = full_chapter_path.to_string_lossy().into_owned();
 
-        let chapter_duration = compute_audio_duration(&full_chapter_path).map_err(|e| {
-            AppError::Internal(format!("failed to compute duration for '{}': {e}", chapter_title))
-        })?;
+        let chapter_duration = match cache
+            .get(&cache_key)
+            .filter(|c| c.size == size && c.mtime_ns == mtime_ns)
+        {
+            Some(cached) => Duration::from_micros(cached.duration_us as u64),
+            None => {
+                tracing::info!("reading duration: {}", chapter_title);
+                compute_audio_duration(&full_chapter_path).map_err(|e| {
+                    AppError::Internal(format!(
+                        "failed to compute duration for '{}': {e}",
+                        chapter_title
+                    ))
+                })?
+            }
+        };
 
         total_duration += chapter_duration;
-        total_size += fs::metadata(&full_chapter_path)?.len();
+        total_size += size as u64;
         chapter_durations.push(chapter_duration);
+        cached_chapters.push(CachedChapter {
+            path: cache_key,
+            size,
+            mtime_ns,
+            duration_us: chapter_duration.as_micros() as i64,
+        });
         chapter_paths.push(full_chapter_path);
     }
 
@@ -234,7 +1015,7 @@ fn scan_audiobook(
 
     let source_checksum = compute_source_checksum(&info_path, &chapter_paths);
     let path = dir.to_string_lossy().into_owned();
-    let hash = compute_hash(author, title, date);
+    let hash = id.unwrap_or_else(|| compute_hash(author, title, date));
 
     let audiobook = Audiobook {
         hash,
@@ -250,9 +1031,12 @@ fn scan_audiobook(
         final_chapter_position,
         cover,
         archive_ready: false,
+        library: library.to_string(),
+        missing_since: None,
+        missing_reason: None,
     };
 
-    Ok((audiobook, chapter_durations.len(), source_checksum))
+    Ok((audiobook, source_checksum, cached_chapters))
 }
 
 pub fn build_archive(dir: &Path) -> Result<(), AppError> {
@@ -383,6 +1167,23 @@ fn compute_audio_duration(path: &Path) -> Result<Duration, AppError> {
     Ok(Duration::from_secs(duration.seconds) + Duration::from_secs_f64(duration.frac))
 }
 
+// An id set in info.yml replaces the hash derived from author, title and date,
+// so the book keeps it whatever else changes.
+fn parse_id(value: &Value) -> Result<Option<String>, String> {
+    if value.is_null() {
+        return Ok(None);
+    }
+    match value.as_str() {
+        Some(id)
+            if (1..=64).contains(&id.len())
+                && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') =>
+        {
+            Ok(Some(id.to_string()))
+        }
+        _ => Err("invalid 'id': expected 1-64 letters, digits, '-' or '_'".to_string()),
+    }
+}
+
 fn compute_hash(author: &str, title: &str, date: i32) -> String {
     let mut hasher = Sha256::new();
     hasher.update(author);
@@ -403,3 +1204,222 @@ fn enqueue_archive(state: &AppState, hash: &str) {
         state.archive_notify.notify_one();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::Config,
+        models::group::Group,
+        repo::{group as group_repo, user as user_repo},
+    };
+
+    struct Harness {
+        state: AppState,
+        dir: PathBuf,
+    }
+
+    impl Drop for Harness {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    impl Harness {
+        fn library(&self) -> PathBuf {
+            self.dir.join("library")
+        }
+
+        async fn scan(&self) -> Result<(), AppError> {
+            let id = claim_scan_job(&self.state).unwrap();
+            run_scan_job(&self.state, id, None).await
+        }
+
+        async fn book(&self, hash: &str) -> Option<Audiobook> {
+            audiobook_repo::find_by_hash(&self.state.db, hash)
+                .await
+                .unwrap()
+        }
+    }
+
+    async fn harness(name: &str) -> Harness {
+        let dir = std::env::temp_dir().join(format!("iliad-library-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let config = Config {
+            db_path: dir.join("iliad.db").to_string_lossy().into_owned(),
+            libraries: vec![(
+                "default".to_string(),
+                dir.join("library").to_string_lossy().into_owned(),
+            )],
+            scan_depth: 3,
+            scan_parallelism: 1,
+            missing_grace_days: 7,
+            watch_library: false,
+            watch_debounce_seconds: 10,
+            scan_interval_minutes: 0,
+            cleanup_interval_minutes: 0,
+            server_address: "127.0.0.1".to_string(),
+            server_port: 0,
+            public_register: false,
+            admin_username: "admin".to_string(),
+            admin_password: None,
+            token_ttl_hours: 1,
+            refresh_ttl_days: 1,
+            sliding_expiry: false,
+            login_max_attempts: 5,
+            login_lockout_seconds: 60,
+            login_lockout_max_seconds: 3600,
+            username_min_length: 3,
+            username_max_length: 32,
+            username_extra_chars: "._-".to_string(),
+            password_min_length: 12,
+            breached_passwords_path: None,
+            oidc_issuer: None,
+            oidc_client_id: None,
+            oidc_client_secret: None,
+            oidc_redirect_url: None,
+            oidc_scopes: "openid profile".to_string(),
+            oidc_username_claim: "preferred_username".to_string(),
+            oidc_auto_provision: true,
+            oidc_link_existing: false,
+            auth_proxy_header: None,
+            trusted_proxies: Vec::new(),
+        };
+        let state = AppState::new(&config).await.unwrap();
+        Harness { state, dir }
+    }
+
+    // One second of 8 kHz mono silence.
+    fn write_wav(path: &Path) {
+        let samples = 8000u32;
+        let data_len = samples * 2;
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes());
+        wav.extend_from_slice(&8000u32.to_le_bytes());
+        wav.extend_from_slice(&16000u32.to_le_bytes());
+        wav.extend_from_slice(&2u16.to_le_bytes());
+        wav.extend_from_slice(&16u16.to_le_bytes());
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&data_len.to_le_bytes());
+        wav.resize(wav.len() + data_len as usize, 0);
+        fs::write(path, wav).unwrap();
+    }
+
+    // Writes a book directory and returns the hash a scan gives it.
+    fn write_book(dir: &Path, title: &str) -> String {
+        fs::create_dir_all(dir).unwrap();
+        fs::write(
+            dir.join("info.yml"),
+            format!(
+                "title: {title}\nauthor: Ann\ndate: 2001\ndescription: test\ngenres: [test]\n\
+                 chapters:\n  - title: One\n    path: ch1.wav\n"
+            ),
+        )
+        .unwrap();
+        if !dir.join("ch1.wav").exists() {
+            write_wav(&dir.join("ch1.wav"));
+        }
+        compute_hash("Ann", title, 2001)
+    }
+
+    #[actix_web::test]
+    async fn vanished_book_is_deleted_after_grace_period() {
+        let harness = harness("grace").await;
+        let gone = write_book(&harness.library().join("gone"), "Gone");
+        write_book(&harness.library().join("kept"), "Kept");
+        harness.scan().await.unwrap();
+
+        fs::remove_dir_all(harness.library().join("gone")).unwrap();
+        harness.scan().await.unwrap();
+        let flagged = harness.book(&gone).await.unwrap();
+        assert!(flagged.missing_since.is_some());
+        assert_eq!(
+            flagged.missing_reason.as_deref(),
+            Some("directory not found")
+        );
+
+        harness.scan().await.unwrap();
+        assert!(harness.book(&gone).await.is_some());
+
+        let since = Utc::now().naive_utc() - chrono::Duration::days(8);
+        sqlx::query("UPDATE audiobooks SET missing_since = ? WHERE hash = ?")
+            .bind(since)
+            .bind(&gone)
+            .execute(&harness.state.db)
+            .await
+            .unwrap();
+        harness.scan().await.unwrap();
+        assert!(harness.book(&gone).await.is_none());
+    }
+
+    #[actix_web::test]
+    async fn empty_library_refuses_scan() {
+        let harness = harness("empty").await;
+        let hash = write_book(&harness.library().join("book"), "Book");
+        harness.scan().await.unwrap();
+
+        fs::remove_dir_all(harness.library().join("book")).unwrap();
+        assert!(harness.scan().await.is_err());
+        let book = harness.book(&hash).await.unwrap();
+        assert!(book.missing_since.is_none());
+    }
+
+    #[actix_web::test]
+    async fn edited_or_moved_book_keeps_positions_and_groups() {
+        let harness = harness("rehash").await;
+        let db = &harness.state.db;
+        let old = write_book(&harness.library().join("book"), "Old Title");
+        harness.scan().await.unwrap();
+
+        let now = Utc::now().naive_utc();
+        user_repo::create(db, "alice", "unused", false)
+            .await
+            .unwrap();
+        position_repo::insert(db, &old, "alice", 0, 500, now)
+            .await
+            .unwrap();
+        let group = Group {
+            name: "kids".to_string(),
+            created_at: now,
+        };
+        group_repo::create(db, &group).await.unwrap();
+        group_repo::add_audiobook(db, "kids", &old).await.unwrap();
+
+        let new = write_book(&harness.library().join("book"), "New Title");
+        harness.scan().await.unwrap();
+        assert!(harness.book(&old).await.is_none());
+        assert!(harness.book(&new).await.is_some());
+        assert_eq!(
+            audiobook_repo::find_alias(db, &old).await.unwrap(),
+            Some(new.clone())
+        );
+        let position = position_repo::find(db, &new, "alice")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(position.chapter_position, 500);
+        assert_eq!(
+            group_repo::find_audiobooks(db, "kids").await.unwrap(),
+            vec![new.clone()]
+        );
+
+        let moved = harness.library().join("moved");
+        fs::rename(harness.library().join("book"), &moved).unwrap();
+        harness.scan().await.unwrap();
+        let book = harness.book(&new).await.unwrap();
+        assert_eq!(book.path, moved.to_string_lossy());
+        assert!(position_repo::find(db, &new, "alice")
+            .await
+            .unwrap()
+            .is_some());
+        assert_eq!(
+            group_repo::find_audiobooks(db, "kids").await.unwrap(),
+            vec![new]
+        );
+    }
+}
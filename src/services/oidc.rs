@@ -0,0 +1,620 @@
+use crate::{
+    error::AppError,
+    inputs::{auth::ClientInfo, oidc::OidcCallback},
+    models::{
+        audit::ACTION_LOGIN,
+        oidc::{OidcIdentity, ProviderMetadata},
+    },
+    outputs::auth::AuthToken,
+    repo::{oidc as oidc_repo, user as user_repo},
+    services::audit,
+    services::auth::{generate_token, issue_session, provision_user},
+    services::validation::{self, check_username},
+    state::{AppState, OidcClient, OidcPending},
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::Utc;
+use jsonwebtoken::{jwk::JwkSet, Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::time::{Duration, Instant};
+
+const PENDING_TTL: Duration = Duration::from_secs(600);
+// Anyone can start a login, so the oldest pending ones are dropped past this.
+const MAX_PENDING: usize = 1000;
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+pub async fn begin_login(state: &AppState) -> Result<String, AppError> {
+    let oidc = state.oidc.as_ref().ok_or(AppError::NotFound)?;
+    let metadata = provider_metadata(oidc).await?;
+
+    let csrf = generate_token();
+    let nonce = generate_token();
+    let verifier = format!("{}{}", generate_token(), generate_token());
+    let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+
+    let url = reqwest::Url::parse_with_params(
+        &metadata.authorization_endpoint,
+        &[
+            ("response_type", "code"),
+            ("client_id", oidc.client_id.as_str()),
+            ("redirect_uri", oidc.redirect_url.as_str()),
+            ("scope", oidc.scopes.as_str()),
+            ("state", csrf.as_str()),
+            ("nonce", nonce.as_str()),
+            ("code_challenge", challenge.as_str()),
+            ("code_challenge_method", "S256"),
+        ],
+    )
+    .map_err(|e| AppError::Internal(format!("invalid authorization endpoint: {e}")))?;
+
+    let mut pending = oidc
+        .pending
+        .lock()
+        .map_err(|_| AppError::Internal("lock poisoned".into()))?;
+    pending.retain(|_, p| p.created_at.elapsed() < PENDING_TTL);
+    if pending.len() >= MAX_PENDING {
+        let oldest = pending
+            .iter()
+            .min_by_key(|(_, p)| p.created_at)
+            .map(|(csrf, _)| csrf.clone());
+        if let Some(oldest) = oldest {
+            pending.remove(&oldest);
+        }
+    }
+    pending.insert(
+        csrf,
+        OidcPending {
+            nonce,
+            verifier,
+            created_at: Instant::now(),
+        },
+    );
+
+    Ok(url.into())
+}
+
+pub async fn finish_login(
+    input: OidcCallback,
+    client: ClientInfo,
+    state: &AppState,
+) -> Result<AuthToken, AppError> {
+    let oidc = state.oidc.as_ref().ok_or(AppError::NotFound)?;
+
+    let csrf = input.state.ok_or(AppError::Unauthorized)?;
+    let pending = oidc
+        .pending
+        .lock()
+        .map_err(|_| AppError::Internal("lock poisoned".into()))?
+        .remove(&csrf)
+        .filter(|p| p.created_at.elapsed() < PENDING_TTL)
+        .ok_or(AppError::Unauthorized)?;
+
+    if let Some(error) = input.error {
+        tracing::warn!(
+            "oidc provider returned {}: {}",
+            error,
+            input.error_description.unwrap_or_default()
+        );
+        return Err(AppError::Unauthorized);
+    }
+    let code = input.code.ok_or(AppError::Unauthorized)?;
+
+    let metadata = provider_metadata(oidc).await?;
+    let id_token = exchange_code(oidc, &metadata, &code, &pending.verifier).await?;
+    let claims = validate_id_token(oidc, &metadata, &id_token, &pending.nonce).await?;
+
+    let subject = claims
+        .get("sub")
+        .and_then(Value::as_str)
+        .ok_or(AppError::Unauthorized)?;
+    let username = match oidc_repo::find(&state.db, &metadata.issuer, subject).await? {
+        Some(identity) => identity.username,
+        None => link_identity(oidc, &metadata, subject, &claims, state).await?,
+    };
+
+    let details = match &client.ip {
+        Some(ip) => format!("via oidc, ip {ip}"),
+        None => "via oidc".to_string(),
+    };
+    audit::record(state, Some(&username), ACTION_LOGIN, None, Some(details)).await;
+    issue_session(state, &username, client).await
+}
+
+async fn link_identity(
+    oidc: &OidcClient,
+    metadata: &ProviderMetadata,
+    subject: &str,
+    claims: &Map<String, Value>,
+    state: &AppState,
+) -> Result<String, AppError> {
+    let claimed = claims
+        .get(&oidc.username_claim)
+        .and_then(Value::as_str)
+        .ok_or_else(|| {
+            tracing::warn!("oidc id token has no {} claim", oidc.username_claim);
+            AppError::Unauthorized
+        })?;
+
+    let mut errors = Vec::new();
+    let username = check_username("username", claimed, state, &mut errors);
+    validation::finish(errors)?;
+
+    let username = match user_repo::find_by_folded_username(&state.db, &username).await? {
+        Some(user) if oidc.link_existing => {
+            tracing::info!("linked oidc subject {} to user {}", subject, user.username);
+            user.username
+        }
+        Some(_) => return Err(AppError::Conflict),
+        None if oidc.auto_provision => {
+            provision_user(state, &username, &format!("oidc subject {subject}")).await?;
+            username
+        }
+        None => return Err(AppError::Unauthorized),
+    };
+
+    oidc_repo::create(
+        &state.db,
+        &OidcIdentity {
+            issuer: metadata.issuer.clone(),
+            subject: subject.to_string(),
+            username: username.clone(),
+            created_at: Utc::now().naive_utc(),
+        },
+    )
+    .await?;
+    Ok(username)
+}
+
+async fn provider_metadata(oidc: &OidcClient) -> Result<ProviderMetadata, AppError> {
+    if let Some(metadata) = oidc.metadata.read().await.as_ref() {
+        return Ok(metadata.clone());
+    }
+
+    let url = format!(
+        "{}/.well-known/openid-configuration",
+        oidc.issuer.trim_end_matches('/')
+    );
+    let metadata: ProviderMetadata = fetch_json(oidc, &url).await?;
+    if metadata.issuer != oidc.issuer {
+        return Err(AppError::Internal(format!(
+            "oidc discovery returned issuer {}, expected {}",
+            metadata.issuer, oidc.issuer
+        )));
+    }
+
+    *oidc.metadata.write().await = Some(metadata.clone());
+    Ok(metadata)
+}
+
+async fn exchange_code(
+    oidc: &OidcClient,
+    metadata: &ProviderMetadata,
+    code: &str,
+    verifier: &str,
+) -> Result<String, AppError> {
+    let mut form = vec![
+        ("grant_type", "authorization_code"),
+        ("code", code),
+        ("redirect_uri", oidc.redirect_url.as_str()),
+        ("client_id", oidc.client_id.as_str()),
+        ("code_verifier", verifier),
+    ];
+    if let Some(secret) = &oidc.client_secret {
+        form.push(("client_secret", secret));
+    }
+
+    let response = oidc
+        .http
+        .post(&metadata.token_endpoint)
+        .form(&form)
+        .send()
+        .await
+        .map_err(|e| AppError::Internal(format!("oidc token request failed: {e}")))?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        tracing::warn!("oidc token endpoint returned {}: {}", status, body);
+        return Err(AppError::Unauthorized);
+    }
+
+    response
+        .json::<TokenResponse>()
+        .await
+        .map(|t| t.id_token)
+        .map_err(|e| AppError::Internal(format!("invalid oidc token response: {e}")))
+}
+
+async fn validate_id_token(
+    oidc: &OidcClient,
+    metadata: &ProviderMetadata,
+    id_token: &str,
+    nonce: &str,
+) -> Result<Map<String, Value>, AppError> {
+    let header = jsonwebtoken::decode_header(id_token).map_err(|_| AppError::Unauthorized)?;
+
+    let key = match header.alg {
+        Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512 => {
+            let secret = oidc.client_secret.as_ref().ok_or(AppError::Unauthorized)?;
+            DecodingKey::from_secret(secret.as_bytes())
+        }
+        _ => signing_key(oidc, metadata, header.kid.as_deref()).await?,
+    };
+
+    let mut validation = Validation::new(header.alg);
+    validation.set_issuer(&[&metadata.issuer]);
+    validation.set_audience(&[&oidc.client_id]);
+    validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+
+    let claims = jsonwebtoken::decode::<Map<String, Value>>(id_token, &key, &validation)
+        .map_err(|e| {
+            tracing::warn!("rejected oidc id token: {}", e);
+            AppError::Unauthorized
+        })?
+        .claims;
+
+    if claims.get("nonce").and_then(Value::as_str) != Some(nonce) {
+        tracing::warn!("rejected oidc id token: nonce mismatch");
+        return Err(AppError::Unauthorized);
+    }
+    Ok(claims)
+}
+
+async fn signing_key(
+    oidc: &OidcClient,
+    metadata: &ProviderMetadata,
+    kid: Option<&str>,
+) -> Result<DecodingKey, AppError> {
+    let find = |jwks: &JwkSet| match kid {
+        Some(kid) => jwks.find(kid).cloned(),
+        None => jwks.keys.first().cloned(),
+    };
+
+    let cached = oidc.jwks.read().await.as_ref().and_then(find);
+    let jwk = match cached {
+        Some(jwk) => jwk,
+        None => {
+            // Unknown key id: the provider may have rotated its keys.
+            let jwks: JwkSet = fetch_json(oidc, &metadata.jwks_uri).await?;
+            let jwk = find(&jwks);
+            *oidc.jwks.write().await = Some(jwks);
+            jwk.ok_or(AppError::Unauthorized)?
+        }
+    };
+
+    DecodingKey::from_jwk(&jwk).map_err(|_| AppError::Unauthorized)
+}
+
+async fn fetch_json<T: serde::de::DeserializeOwned>(
+    oidc: &OidcClient,
+    url: &str,
+) -> Result<T, AppError> {
+    oidc.http
+        .get(url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| AppError::Internal(format!("oidc request to {url} failed: {e}")))?
+        .json()
+        .await
+        .map_err(|e| AppError::Internal(format!("invalid oidc response from {url}: {e}")))
+}
+
+// The login flow against a mock issuer serving discovery, an empty key set and
+// a token endpoint that checks PKCE before signing an HS256 id token.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::Config, error::AppError, repo::audit as audit_repo};
+    use actix_web::{web, App, HttpResponse, HttpServer};
+    use jsonwebtoken::{EncodingKey, Header};
+    use serde_json::json;
+    use std::{collections::HashMap, net::TcpListener, path::PathBuf, sync::Mutex};
+
+    const CLIENT_ID: &str = "iliad";
+    const CLIENT_SECRET: &str = "mock-secret";
+    const REDIRECT_URL: &str = "http://iliad.test/auth/oidc/callback";
+
+    struct Grant {
+        challenge: String,
+        claims: Value,
+    }
+
+    struct MockIssuer {
+        issuer: String,
+        advertised_issuer: String,
+        grants: Mutex<HashMap<String, Grant>>,
+    }
+
+    async fn discovery(mock: web::Data<MockIssuer>) -> HttpResponse {
+        HttpResponse::Ok().json(json!({
+            "issuer": mock.advertised_issuer,
+            "authorization_endpoint": format!("{}/authorize", mock.issuer),
+            "token_endpoint": format!("{}/token", mock.issuer),
+            "jwks_uri": format!("{}/jwks", mock.issuer),
+        }))
+    }
+
+    async fn jwks() -> HttpResponse {
+        HttpResponse::Ok().json(json!({ "keys": [] }))
+    }
+
+    async fn token(
+        mock: web::Data<MockIssuer>,
+        form: web::Form<HashMap<String, String>>,
+    ) -> HttpResponse {
+        let field = |name: &str| form.get(name).map(String::as_str).unwrap_or_default();
+        let grant = mock.grants.lock().unwrap().remove(field("code"));
+        let Some(grant) = grant else {
+            return HttpResponse::BadRequest().json(json!({ "error": "invalid_grant" }));
+        };
+        let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(field("code_verifier").as_bytes()));
+        if field("grant_type") != "authorization_code"
+            || field("client_id") != CLIENT_ID
+            || field("client_secret") != CLIENT_SECRET
+            || field("redirect_uri") != REDIRECT_URL
+            || challenge != grant.challenge
+        {
+            return HttpResponse::BadRequest().json(json!({ "error": "invalid_grant" }));
+        }
+        let id_token = jsonwebtoken::encode(
+            &Header::new(Algorithm::HS256),
+            &grant.claims,
+            &EncodingKey::from_secret(CLIENT_SECRET.as_bytes()),
+        )
+        .unwrap();
+        HttpResponse::Ok().json(json!({ "access_token": "unused", "id_token": id_token }))
+    }
+
+    struct Harness {
+        mock: web::Data<MockIssuer>,
+        state: AppState,
+        dir: PathBuf,
+    }
+
+    impl Drop for Harness {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    async fn harness(name: &str, advertise_other_issuer: bool) -> Harness {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let issuer = format!("http://{}", listener.local_addr().unwrap());
+        let mock = web::Data::new(MockIssuer {
+            advertised_issuer: if advertise_other_issuer {
+                "http://elsewhere.test".to_string()
+            } else {
+                issuer.clone()
+            },
+            issuer: issuer.clone(),
+            grants: Mutex::new(HashMap::new()),
+        });
+        let app_mock = mock.clone();
+        let server = HttpServer::new(move || {
+            App::new()
+                .app_data(app_mock.clone())
+                .route(
+                    "/.well-known/openid-configuration",
+                    web::get().to(discovery),
+                )
+                .route("/jwks", web::get().to(jwks))
+                .route("/token", web::post().to(token))
+        })
+        .workers(1)
+        .listen(listener)
+        .unwrap()
+        .run();
+        actix_web::rt::spawn(server);
+
+        let dir = std::env::temp_dir().join(format!("iliad-oidc-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let config = Config {
+            db_path: dir.join("iliad.db").to_string_lossy().into_owned(),
+            libraries: vec![(
+                "default".to_string(),
+                dir.join("library").to_string_lossy().into_owned(),
+            )],
+            scan_depth: 3,
+            scan_parallelism: 1,
+            missing_grace_days: 7,
+            watch_library: false,
+            watch_debounce_seconds: 10,
+            scan_interval_minutes: 0,
+            cleanup_interval_minutes: 0,
+            server_address: "127.0.0.1".to_string(),
+            server_port: 0,
+            public_register: false,
+            admin_username: "admin".to_string(),
+            admin_password: None,
+            token_ttl_hours: 1,
+            refresh_ttl_days: 1,
+            sliding_expiry: false,
+            login_max_attempts: 5,
+            login_lockout_seconds: 60,
+            login_lockout_max_seconds: 3600,
+            username_min_length: 3,
+            username_max_length: 32,
+            username_extra_chars: "._-".to_string(),
+            password_min_length: 12,
+            breached_passwords_path: None,
+            oidc_issuer: Some(issuer),
+            oidc_client_id: Some(CLIENT_ID.to_string()),
+            oidc_client_secret: Some(CLIENT_SECRET.to_string()),
+            oidc_redirect_url: Some(REDIRECT_URL.to_string()),
+            oidc_scopes: "openid profile".to_string(),
+            oidc_username_claim: "preferred_username".to_string(),
+            oidc_auto_provision: true,
+            oidc_link_existing: false,
+            auth_proxy_header: None,
+            trusted_proxies: Vec::new(),
+        };
+        let state = AppState::new(&config).await.unwrap();
+        Harness { mock, state, dir }
+    }
+
+    fn client() -> ClientInfo {
+        ClientInfo {
+            client_name: None,
+            user_agent: None,
+            ip: Some("127.0.0.1".to_string()),
+        }
+    }
+
+    // Plays the provider's authorization page: checks the request, then grants
+    // a code whose id token carries the claims changed by edit.
+    async fn authorize(
+        harness: &Harness,
+        challenge: Option<&str>,
+        edit: impl FnOnce(&mut Map<String, Value>),
+    ) -> OidcCallback {
+        let url = reqwest::Url::parse(&begin_login(&harness.state).await.unwrap()).unwrap();
+        assert!(url
+            .as_str()
+            .starts_with(&format!("{}/authorize?", harness.mock.issuer)));
+        let params: HashMap<String, String> = url.query_pairs().into_owned().collect();
+        assert_eq!(params["response_type"], "code");
+        assert_eq!(params["client_id"], CLIENT_ID);
+        assert_eq!(params["redirect_uri"], REDIRECT_URL);
+        assert_eq!(params["code_challenge_method"], "S256");
+
+        let mut claims = Map::new();
+        claims.insert("iss".into(), json!(harness.mock.issuer));
+        claims.insert("aud".into(), json!(CLIENT_ID));
+        claims.insert("sub".into(), json!("subject-1"));
+        claims.insert("exp".into(), json!(Utc::now().timestamp() + 300));
+        claims.insert("nonce".into(), json!(params["nonce"]));
+        claims.insert("preferred_username".into(), json!("alice"));
+        edit(&mut claims);
+
+        let code = generate_token();
+        harness.mock.grants.lock().unwrap().insert(
+            code.clone(),
+            Grant {
+                challenge: challenge.unwrap_or(&params["code_challenge"]).to_string(),
+                claims: Value::Object(claims),
+            },
+        );
+        OidcCallback {
+            code: Some(code),
+            state: Some(params["state"].clone()),
+            error: None,
+            error_description: None,
+        }
+    }
+
+    #[actix_web::test]
+    async fn login_provisions_and_audits_user() {
+        let harness = harness("login", false).await;
+        let callback = authorize(&harness, None, |_| {}).await;
+        finish_login(callback, client(), &harness.state)
+            .await
+            .unwrap();
+
+        let identity = oidc_repo::find(&harness.state.db, &harness.mock.issuer, "subject-1")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(identity.username, "alice");
+        let events = audit_repo::find(
+            &harness.state.db,
+            Some("alice"),
+            Some(ACTION_LOGIN),
+            None,
+            None,
+            10,
+        )
+        .await
+        .unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].details.as_deref(), Some("via oidc, ip 127.0.0.1"));
+
+        // The subject stays linked even if the provider's username changes.
+        let callback = authorize(&harness, None, |claims| {
+            claims.insert("preferred_username".into(), json!("alice2"));
+        })
+        .await;
+        finish_login(callback, client(), &harness.state)
+            .await
+            .unwrap();
+        assert!(user_repo::find_by_username(&harness.state.db, "alice2")
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[actix_web::test]
+    async fn caps_pending_logins() {
+        let harness = harness("pending", false).await;
+        for _ in 0..MAX_PENDING + 10 {
+            begin_login(&harness.state).await.unwrap();
+        }
+        let oidc = harness.state.oidc.as_ref().unwrap();
+        assert_eq!(oidc.pending.lock().unwrap().len(), MAX_PENDING);
+    }
+
+    #[actix_web::test]
+    async fn rejects_reused_state() {
+        let harness = harness("state", false).await;
+        let callback = authorize(&harness, None, |_| {}).await;
+        let replay = OidcCallback {
+            code: callback.code.clone(),
+            state: callback.state.clone(),
+            error: None,
+            error_description: None,
+        };
+        finish_login(callback, client(), &harness.state)
+            .await
+            .unwrap();
+        let result = finish_login(replay, client(), &harness.state).await;
+        assert!(matches!(result, Err(AppError::Unauthorized)));
+    }
+
+    #[actix_web::test]
+    async fn rejects_wrong_nonce() {
+        let harness = harness("nonce", false).await;
+        let callback = authorize(&harness, None, |claims| {
+            claims.insert("nonce".into(), json!("replayed"));
+        })
+        .await;
+        let result = finish_login(callback, client(), &harness.state).await;
+        assert!(matches!(result, Err(AppError::Unauthorized)));
+    }
+
+    #[actix_web::test]
+    async fn rejects_wrong_audience() {
+        let harness = harness("audience", false).await;
+        let callback = authorize(&harness, None, |claims| {
+            claims.insert("aud".into(), json!("another-client"));
+        })
+        .await;
+        let result = finish_login(callback, client(), &harness.state).await;
+        assert!(matches!(result, Err(AppError::Unauthorized)));
+    }
+
+    #[actix_web::test]
+    async fn rejects_wrong_pkce_verifier() {
+        let harness = harness("pkce", false).await;
+        let other = URL_SAFE_NO_PAD.encode(Sha256::digest(b"another verifier"));
+        let callback = authorize(&harness, Some(&other), |_| {}).await;
+        let result = finish_login(callback, client(), &harness.state).await;
+        assert!(matches!(result, Err(AppError::Unauthorized)));
+        assert!(user_repo::find_by_username(&harness.state.db, "alice")
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[actix_web::test]
+    async fn rejects_discovery_for_another_issuer() {
+        let harness = harness("discovery", true).await;
+        let result = begin_login(&harness.state).await;
+        assert!(matches!(result, Err(AppError::Internal(_))));
+    }
+}
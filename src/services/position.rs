@@ -3,6 +3,7 @@ use crate::{
     inputs::position::InputPositionUpdate,
     outputs::position::OutputPositionUpdate,
     repo::position as position_repo,
+    services::{audiobook::resolve_hash, group::ensure_access},
     state::AppState,
 };
 use chrono::{DateTime, Utc};
@@ -12,6 +13,8 @@ pub async fn get_position_user_book(
     book_hash: String,
     state: &AppState,
 ) -> Result<OutputPositionUpdate, AppError> {
+    let book_hash = resolve_hash(book_hash, state).await?;
+    ensure_access(&username, &book_hash, state).await?;
     let position = position_repo::find(&state.db, &book_hash, &username).await?;
 
     match position {
@@ -34,6 +37,8 @@ pub async fn put_position_user_book(
     input: InputPositionUpdate,
     state: &AppState,
 ) -> Result<(), AppError> {
+    let book_hash = resolve_hash(book_hash, state).await?;
+    ensure_access(&username, &book_hash, state).await?;
     let timestamp = DateTime::<Utc>::from_timestamp(input.timestamp, 0)
         .ok_or_else(|| AppError::Internal("invalid client timestamp".into()))?
         .naive_utc();
@@ -0,0 +1,91 @@
+use crate::{
+    config::Config,
+    outputs::library::{ScheduledTaskInfo, TaskRunInfo},
+    services::library::{run_cleanup, scheduled_scan},
+    state::{AppState, ScheduledTask, TaskKind, TaskRun},
+};
+use chrono::Utc;
+use std::time::Duration;
+use tokio::time::{interval_at, Instant, MissedTickBehavior};
+
+// Runs every enabled task on its own interval, an interval of 0 disables a
+// task. The first run is one interval after the scheduler starts, the startup
+// scan already covers the library. A slow run delays the next one instead of
+// letting runs pile up.
+pub fn spawn_scheduler(state: &AppState, config: &Config) {
+    let tasks = [
+        (TaskKind::Scan, config.scan_interval_minutes),
+        (TaskKind::Cleanup, config.cleanup_interval_minutes),
+    ];
+
+    for (kind, minutes) in tasks.into_iter().filter(|(_, minutes)| *minutes > 0) {
+        let interval = Duration::from_secs(minutes * 60);
+        state.schedule.lock().unwrap().push(ScheduledTask {
+            kind,
+            interval,
+            next_run_at: Utc::now().naive_utc() + chrono::Duration::minutes(minutes as i64),
+            last_run: None,
+        });
+
+        let state = state.clone();
+        tokio::spawn(async move {
+            let mut ticker = interval_at(Instant::now() + interval, interval);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                run_task(&state, kind).await;
+            }
+        });
+    }
+}
+
+async fn run_task(state: &AppState, kind: TaskKind) {
+    let started_at = Utc::now().naive_utc();
+    tracing::info!("running scheduled {}", kind.as_str());
+    let result = match kind {
+        TaskKind::Scan => scheduled_scan(state).await,
+        TaskKind::Cleanup => run_cleanup(None, state).await,
+    };
+    if let Err(e) = &result {
+        tracing::error!("scheduled {} failed: {}", kind.as_str(), e);
+    }
+
+    let finished_at = Utc::now().naive_utc();
+    let mut schedule = state.schedule.lock().unwrap();
+    if let Some(task) = schedule.iter_mut().find(|t| t.kind == kind) {
+        task.next_run_at = started_at
+            + chrono::Duration::from_std(task.interval).unwrap_or_default();
+        if task.next_run_at < finished_at {
+            task.next_run_at = finished_at;
+        }
+        task.last_run = Some(TaskRun {
+            started_at,
+            finished_at,
+            success: result.is_ok(),
+            message: match result {
+                Ok(summary) => summary,
+                Err(e) => e.to_string(),
+            },
+        });
+    }
+}
+
+pub fn list_tasks(state: &AppState) -> Vec<ScheduledTaskInfo> {
+    state
+        .schedule
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|task| ScheduledTaskInfo {
+            task: task.kind.as_str(),
+            interval_minutes: task.interval.as_secs() / 60,
+            next_run_at: task.next_run_at.and_utc().timestamp(),
+            last_run: task.last_run.as_ref().map(|run| TaskRunInfo {
+                started_at: run.started_at.and_utc().timestamp(),
+                finished_at: run.finished_at.and_utc().timestamp(),
+                success: run.success,
+                message: run.message.clone(),
+            }),
+        })
+        .collect()
+}
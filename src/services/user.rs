@@ -0,0 +1,146 @@
+use crate::{
+    error::AppError,
+    inputs::user::{AdminGrant, PasswordReset, UserRename},
+    models::{
+        audit::{ACTION_USER_ADMIN, ACTION_USER_DELETE, ACTION_USER_PASSWORD_RESET, ACTION_USER_RENAME},
+        user::User,
+    },
+    outputs::user::UserInfo,
+    repo::{api_key as api_key_repo, session as session_repo, user as user_repo},
+    services::audit,
+    services::auth::hash_password,
+    services::validation::{self, check_password, check_username},
+    state::AppState,
+};
+
+pub async fn list_users(state: &AppState) -> Result<Vec<UserInfo>, AppError> {
+    let users = user_repo::find_all(&state.db).await?;
+    Ok(users.into_iter().map(user_info).collect())
+}
+
+pub async fn get_user(username: String, state: &AppState) -> Result<UserInfo, AppError> {
+    let user = user_repo::find_by_username(&state.db, &username)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    Ok(user_info(user))
+}
+
+pub async fn delete_user(actor: String, username: String, state: &AppState) -> Result<(), AppError> {
+    let user = user_repo::find_by_username(&state.db, &username)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    if user.is_admin && user_repo::count_admins(&state.db).await? <= 1 {
+        return Err(AppError::Conflict);
+    }
+
+    user_repo::delete(&state.db, &username).await?;
+    tracing::info!("deleted user {}", username);
+    audit::record(state, Some(&actor), ACTION_USER_DELETE, Some(&username), None).await;
+    Ok(())
+}
+
+pub async fn reset_password(
+    actor: String,
+    username: String,
+    input: PasswordReset,
+    state: &AppState,
+) -> Result<(), AppError> {
+    user_repo::find_by_username(&state.db, &username)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    let mut errors = Vec::new();
+    check_password("password", &input.password, state, &mut errors);
+    validation::finish(errors)?;
+
+    let password_hash = hash_password(&input.password)?;
+    user_repo::update_password(&state.db, &username, &password_hash).await?;
+    session_repo::delete_by_username(&state.db, &username).await?;
+    api_key_repo::delete_by_username(&state.db, &username).await?;
+    tracing::info!("reset password of user {}", username);
+    audit::record(
+        state,
+        Some(&actor),
+        ACTION_USER_PASSWORD_RESET,
+        Some(&username),
+        None,
+    )
+    .await;
+    Ok(())
+}
+
+pub async fn set_admin(
+    actor: String,
+    username: String,
+    input: AdminGrant,
+    state: &AppState,
+) -> Result<UserInfo, AppError> {
+    let user = user_repo::find_by_username(&state.db, &username)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    if user.is_admin && !input.is_admin && user_repo::count_admins(&state.db).await? <= 1 {
+        return Err(AppError::Conflict);
+    }
+
+    user_repo::set_admin(&state.db, &username, input.is_admin).await?;
+    tracing::info!("set admin role of user {} to {}", username, input.is_admin);
+    audit::record(
+        state,
+        Some(&actor),
+        ACTION_USER_ADMIN,
+        Some(&username),
+        Some(format!("is_admin {}", input.is_admin)),
+    )
+    .await;
+    Ok(UserInfo {
+        username,
+        is_admin: input.is_admin,
+    })
+}
+
+pub async fn rename_user(
+    actor: String,
+    username: String,
+    input: UserRename,
+    state: &AppState,
+) -> Result<UserInfo, AppError> {
+    let user = user_repo::find_by_username(&state.db, &username)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    let mut errors = Vec::new();
+    let new_username = check_username("username", &input.username, state, &mut errors);
+    validation::finish(errors)?;
+
+    // Allow changing only the case of a name, but not taking someone else's.
+    if user_repo::find_by_folded_username(&state.db, &new_username)
+        .await?
+        .is_some_and(|other| other.username != username)
+    {
+        return Err(AppError::Conflict);
+    }
+
+    user_repo::rename(&state.db, &username, &new_username).await?;
+    tracing::info!("renamed user {} to {}", username, new_username);
+    audit::record(
+        state,
+        Some(&actor),
+        ACTION_USER_RENAME,
+        Some(&username),
+        Some(format!("to {new_username}")),
+    )
+    .await;
+    Ok(UserInfo {
+        username: new_username,
+        is_admin: user.is_admin,
+    })
+}
+
+fn user_info(user: User) -> UserInfo {
+    UserInfo {
+        username: user.username,
+        is_admin: user.is_admin,
+    }
+}
@@ -0,0 +1,69 @@
+use crate::{
+    error::{AppError, FieldError},
+    models::user::normalize_username,
+    state::AppState,
+};
+
+pub fn check_username(
+    field: &'static str,
+    username: &str,
+    state: &AppState,
+    errors: &mut Vec<FieldError>,
+) -> String {
+    let username = normalize_username(username);
+    let length = username.chars().count();
+
+    if length < state.username_min_length || length > state.username_max_length {
+        errors.push(FieldError {
+            field,
+            message: format!(
+                "must be between {} and {} characters long",
+                state.username_min_length, state.username_max_length
+            ),
+        });
+    }
+
+    if let Some(c) = username
+        .chars()
+        .find(|c| !c.is_alphanumeric() && !state.username_extra_chars.contains(*c))
+    {
+        errors.push(FieldError {
+            field,
+            message: format!("must not contain '{}'", c.escape_default()),
+        });
+    }
+
+    username
+}
+
+pub fn check_password(
+    field: &'static str,
+    password: &str,
+    state: &AppState,
+    errors: &mut Vec<FieldError>,
+) {
+    if password.chars().count() < state.password_min_length {
+        errors.push(FieldError {
+            field,
+            message: format!(
+                "must be at least {} characters long",
+                state.password_min_length
+            ),
+        });
+    }
+
+    if state.breached_passwords.contains(password) {
+        errors.push(FieldError {
+            field,
+            message: "appears in a list of breached passwords".into(),
+        });
+    }
+}
+
+pub fn finish(errors: Vec<FieldError>) -> Result<(), AppError> {
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(AppError::InvalidInput(errors))
+    }
+}
@@ -0,0 +1,72 @@
+use crate::{error::AppError, services::library::rescan_dirs, state::AppState};
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+// Watches every library root and rescans the directories that changed once no
+// new events arrived for the debounce period. A rescan that collides with a
+// running scan is retried after the next quiet period.
+pub fn spawn_watcher(state: AppState, debounce: Duration) -> Result<(), AppError> {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<Event>| {
+        let _ = tx.send(res);
+    })
+    .map_err(|e| AppError::Internal(format!("cannot start library watcher: {e}")))?;
+    for library in &state.libraries {
+        watcher
+            .watch(&library.path, RecursiveMode::Recursive)
+            .map_err(|e| AppError::Internal(format!("cannot watch {:?}: {e}", library.path)))?;
+    }
+
+    tokio::spawn(async move {
+        let _watcher = watcher;
+        let mut pending: HashSet<PathBuf> = HashSet::new();
+        loop {
+            let received = if pending.is_empty() {
+                rx.recv().await
+            } else {
+                match tokio::time::timeout(debounce, rx.recv()).await {
+                    Ok(received) => received,
+                    Err(_) => {
+                        let dirs: Vec<PathBuf> = pending.iter().cloned().collect();
+                        tracing::info!("library changed, rescanning {} directories", dirs.len());
+                        match rescan_dirs(&state, &dirs).await {
+                            Ok(true) => pending.clear(),
+                            Ok(false) => tracing::debug!("scan in progress, delaying rescan"),
+                            Err(e) => {
+                                tracing::error!("library rescan failed: {}", e);
+                                pending.clear();
+                            }
+                        }
+                        continue;
+                    }
+                }
+            };
+
+            match received {
+                Some(Ok(event)) => {
+                    if matches!(event.kind, EventKind::Access(_)) {
+                        continue;
+                    }
+                    for path in event.paths {
+                        // Archives are written into book directories by the
+                        // archive worker and must not trigger rescans.
+                        if path.file_name().is_some_and(|n| n == "archive.tar.gz") {
+                            continue;
+                        }
+                        if path.is_dir() {
+                            pending.insert(path);
+                        } else if let Some(parent) = path.parent() {
+                            pending.insert(parent.to_path_buf());
+                        }
+                    }
+                }
+                Some(Err(e)) => tracing::warn!("library watcher error: {}", e),
+                None => break,
+            }
+        }
+    });
+    Ok(())
+}
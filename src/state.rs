@@ -1,28 +1,172 @@
 use crate::{db, error::AppError};
+use chrono::NaiveDateTime;
 use sqlx::SqlitePool;
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs;
+use std::net::IpAddr;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
-use tokio::sync::Notify;
+use tokio::sync::{Notify, RwLock};
+
+use crate::models::oidc::ProviderMetadata;
+use crate::outputs::library::ScanReport;
 
 pub struct ArchiveQueue {
     pub pending: VecDeque<String>,
     pub in_progress: Option<String>,
 }
 
+#[derive(Clone, Copy, PartialEq)]
+pub enum ScanState {
+    Running,
+    Finished,
+    Failed,
+}
+
+impl ScanState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ScanState::Running => "running",
+            ScanState::Finished => "finished",
+            ScanState::Failed => "failed",
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ScanError {
+    pub path: String,
+    pub message: String,
+}
+
+#[derive(Clone)]
+pub struct ScanJob {
+    pub id: u64,
+    pub state: ScanState,
+    pub started_at: NaiveDateTime,
+    pub finished_at: Option<NaiveDateTime>,
+    pub books_total: usize,
+    pub books_processed: usize,
+    pub chapters_total: usize,
+    pub chapters_processed: usize,
+    pub errors: Vec<ScanError>,
+    pub error: Option<String>,
+}
+
+pub struct ScanJobs {
+    pub next_id: u64,
+    pub jobs: VecDeque<ScanJob>,
+}
+
+pub struct ReportJob {
+    pub id: u64,
+    pub state: ScanState,
+    pub started_at: NaiveDateTime,
+    pub finished_at: Option<NaiveDateTime>,
+    pub report: Option<ScanReport>,
+    pub error: Option<String>,
+}
+
+pub struct ReportJobs {
+    pub next_id: u64,
+    pub jobs: VecDeque<ReportJob>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum TaskKind {
+    Scan,
+    Cleanup,
+}
+
+impl TaskKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TaskKind::Scan => "scan",
+            TaskKind::Cleanup => "cleanup",
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct TaskRun {
+    pub started_at: NaiveDateTime,
+    pub finished_at: NaiveDateTime,
+    pub success: bool,
+    pub message: String,
+}
+
+#[derive(Clone)]
+pub struct ScheduledTask {
+    pub kind: TaskKind,
+    pub interval: Duration,
+    pub next_run_at: NaiveDateTime,
+    pub last_run: Option<TaskRun>,
+}
+
+pub struct LoginAttempts {
+    pub failures: u32,
+    pub last_failure: Instant,
+    pub locked_until: Option<Instant>,
+}
+
+pub struct OidcPending {
+    pub nonce: String,
+    pub verifier: String,
+    pub created_at: Instant,
+}
+
+pub struct OidcClient {
+    pub issuer: String,
+    pub client_id: String,
+    pub client_secret: Option<String>,
+    pub redirect_url: String,
+    pub scopes: String,
+    pub username_claim: String,
+    pub auto_provision: bool,
+    pub link_existing: bool,
+
+    pub http: reqwest::Client,
+    pub metadata: RwLock<Option<ProviderMetadata>>,
+    pub jwks: RwLock<Option<jsonwebtoken::jwk::JwkSet>>,
+    pub pending: Mutex<HashMap<String, OidcPending>>,
+}
+
+#[derive(Clone)]
+pub struct Library {
+    pub name: String,
+    pub path: PathBuf,
+}
+
 #[derive(Clone)]
 pub struct AppState {
     pub db: SqlitePool,
-    pub library_path: PathBuf,
-    pub admin_password: String,
+    pub libraries: Vec<Library>,
+    pub scan_depth: usize,
+    pub scan_parallelism: usize,
+    pub missing_grace_days: u64,
+    pub public_register: bool,
     pub token_ttl: Duration,
+    pub refresh_ttl: Duration,
+    pub sliding_expiry: bool,
+    pub login_max_attempts: u32,
+    pub login_lockout: Duration,
+    pub login_lockout_max: Duration,
+    pub username_min_length: usize,
+    pub username_max_length: usize,
+    pub username_extra_chars: String,
+    pub password_min_length: usize,
+    pub auth_proxy_header: Option<String>,
+    pub trusted_proxies: Vec<IpAddr>,
 
-    pub regular_tokens: Arc<Mutex<HashMap<String, (String, Instant)>>>,
-    pub admin_tokens: Arc<Mutex<Vec<(String, Instant)>>>,
+    pub breached_passwords: Arc<HashSet<String>>,
+    pub oidc: Option<Arc<OidcClient>>,
+    pub login_attempts: Arc<Mutex<HashMap<String, LoginAttempts>>>,
     pub archive_queue: Arc<Mutex<ArchiveQueue>>,
     pub archive_notify: Arc<Notify>,
+    pub scan_jobs: Arc<Mutex<ScanJobs>>,
+    pub report_jobs: Arc<Mutex<ReportJobs>>,
+    pub schedule: Arc<Mutex<Vec<ScheduledTask>>>,
 }
 
 impl AppState {
@@ -31,23 +175,118 @@ impl AppState {
             fs::create_dir_all(db_dir)?;
         }
 
-        let library_path = PathBuf::from(&config.library_path);
-        fs::create_dir_all(&library_path)?;
+        let mut libraries = Vec::new();
+        for (name, path) in &config.libraries {
+            let path = PathBuf::from(path);
+            fs::create_dir_all(&path)?;
+            libraries.push(Library {
+                name: name.clone(),
+                path,
+            });
+        }
 
         let db = db::connect(&config.db_path).await?;
 
+        let breached_passwords = match &config.breached_passwords_path {
+            Some(path) => {
+                let passwords: HashSet<String> = fs::read_to_string(path)
+                    .map_err(|e| AppError::Internal(format!("cannot read {path}: {e}")))?
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty())
+                    .map(String::from)
+                    .collect();
+                tracing::info!("loaded {} breached passwords", passwords.len());
+                passwords
+            }
+            None => HashSet::new(),
+        };
+
+        let oidc = match &config.oidc_issuer {
+            Some(issuer) => {
+                let missing = |name: &str| AppError::Internal(format!("{name} must be set when ILIAD_OIDC_ISSUER is"));
+                Some(Arc::new(OidcClient {
+                    issuer: issuer.clone(),
+                    client_id: config
+                        .oidc_client_id
+                        .clone()
+                        .ok_or_else(|| missing("ILIAD_OIDC_CLIENT_ID"))?,
+                    client_secret: config.oidc_client_secret.clone(),
+                    redirect_url: config
+                        .oidc_redirect_url
+                        .clone()
+                        .ok_or_else(|| missing("ILIAD_OIDC_REDIRECT_URL"))?,
+                    scopes: config.oidc_scopes.clone(),
+                    username_claim: config.oidc_username_claim.clone(),
+                    auto_provision: config.oidc_auto_provision,
+                    link_existing: config.oidc_link_existing,
+                    http: reqwest::Client::builder()
+                        .timeout(Duration::from_secs(10))
+                        .build()
+                        .map_err(|e| AppError::Internal(format!("cannot build http client: {e}")))?,
+                    metadata: RwLock::new(None),
+                    jwks: RwLock::new(None),
+                    pending: Mutex::new(HashMap::new()),
+                }))
+            }
+            None => None,
+        };
+
+        let token_ttl = config
+            .token_ttl_hours
+            .checked_mul(3600)
+            .map(Duration::from_secs)
+            .ok_or_else(|| AppError::Internal("ILIAD_TOKEN_TTL_HOURS is too large".into()))?;
+        let refresh_ttl = config
+            .refresh_ttl_days
+            .checked_mul(24 * 3600)
+            .map(Duration::from_secs)
+            .ok_or_else(|| AppError::Internal("ILIAD_REFRESH_TTL_DAYS is too large".into()))?;
+        if Instant::now()
+            .checked_add(Duration::from_secs(config.login_lockout_max_seconds))
+            .is_none()
+        {
+            return Err(AppError::Internal(
+                "ILIAD_LOGIN_LOCKOUT_MAX_SECONDS is too large".into(),
+            ));
+        }
+
         Ok(Self {
             db,
-            library_path,
-            admin_password: config.admin_password.clone(),
-            token_ttl: Duration::from_secs(config.token_ttl_hours * 3600),
-            regular_tokens: Arc::new(Mutex::new(HashMap::new())),
-            admin_tokens: Arc::new(Mutex::new(Vec::new())),
+            libraries,
+            scan_depth: config.scan_depth,
+            scan_parallelism: config.scan_parallelism,
+            missing_grace_days: config.missing_grace_days,
+            public_register: config.public_register,
+            token_ttl,
+            refresh_ttl,
+            sliding_expiry: config.sliding_expiry,
+            login_max_attempts: config.login_max_attempts,
+            login_lockout: Duration::from_secs(config.login_lockout_seconds),
+            login_lockout_max: Duration::from_secs(config.login_lockout_max_seconds),
+            username_min_length: config.username_min_length,
+            username_max_length: config.username_max_length,
+            username_extra_chars: config.username_extra_chars.clone(),
+            password_min_length: config.password_min_length,
+            auth_proxy_header: config.auth_proxy_header.clone(),
+            trusted_proxies: config.trusted_proxies.clone(),
+            breached_passwords: Arc::new(breached_passwords),
+            oidc,
+            login_attempts: Arc::new(Mutex::new(HashMap::new())),
             archive_queue: Arc::new(Mutex::new(ArchiveQueue {
                 pending: VecDeque::new(),
                 in_progress: None,
             })),
             archive_notify: Arc::new(Notify::new()),
+            scan_jobs: Arc::new(Mutex::new(ScanJobs {
+                next_id: 1,
+                jobs: VecDeque::new(),
+            })),
+            report_jobs: Arc::new(Mutex::new(ReportJobs {
+                next_id: 1,
+                jobs: VecDeque::new(),
+            })),
+            schedule: Arc::new(Mutex::new(Vec::new())),
         })
     }
 }